@@ -16,12 +16,19 @@ pub fn generate_ast(output_dir: &str) -> io::Result<()> {
         &[
             "Assign   : Token name, Rc<Expr> value",
             "Binary   : Rc<Expr> left, Token operator, Rc<Expr> right",
-            "Call : Rc<Expr> callee, Token paren, Vec<Rc<Expr>> arguments",
+            "Call : Rc<Expr> callee, Token paren, Vec<Rc<Expr>> arguments, Vec<Token> names, Vec<Rc<Expr>> named_arguments",
             "Get : Rc<Expr> object, Token name",
             "Grouping : Rc<Expr> expression",
+            "Index : Rc<Expr> object, Token bracket, Rc<Expr> index",
+            "List : Vec<Rc<Expr>> elements",
             "Literal  : Option<Object> value",
             "Logical : Rc<Expr> left, Token operator, Rc<Expr> right",
+            "Map : Token brace, Vec<Rc<Expr>> keys, Vec<Rc<Expr>> values",
+            "OptionalChain : Rc<Expr> chain",
+            "OptionalGet : Rc<Expr> object, Token name",
+            "Range : Rc<Expr> start, Token operator, Rc<Expr> end, Option<Rc<Expr>> step",
             "Set : Rc<Expr> object, Token name, Rc<Expr> value",
+            "SetIndex : Rc<Expr> object, Token bracket, Rc<Expr> index, Rc<Expr> value",
             "Super: Token keyword, Token method",
             "This : Token keyword",
             "Unary    : Token operator, Rc<Expr> right",
@@ -32,18 +39,40 @@ pub fn generate_ast(output_dir: &str) -> io::Result<()> {
     define_ast(
         output_dir,
         "Stmt",
-        &["error", "expr", "token", "rc"],
+        &["error", "expr", "pattern", "token", "rc"],
         &[
             "Block : Rc<Vec<Rc<Stmt>>> statements",
-            "Class : Token name, Option<Rc<Expr>> superclass, Rc<Vec<Rc<Stmt>>> methods",
+            "Class : Token name, Option<Rc<Vec<Token>>> fields, Option<Rc<Expr>> superclass, Rc<Vec<Rc<Expr>>> traits, Rc<Vec<Rc<Stmt>>> methods, Rc<Vec<Token>> abstract_methods",
             "Break : Token token",
+            "Destructure : Option<Token> keyword, Token equals, Rc<Pattern> pattern, Rc<Expr> value",
+            "Enum : Token name, Rc<Vec<Token>> variants, Rc<Vec<Option<Vec<Token>>>> payloads",
             "Expression : Rc<Expr> expression",
-            "Function : Token name, Rc<Vec<Token>> params, Rc<Vec<Rc<Stmt>>> body",
+            "ForIn : Token name, Rc<Expr> iterable, Rc<Stmt> body",
+            "Function : Token name, Rc<Vec<Token>> params, Rc<Vec<Option<Rc<Expr>>>> defaults, Option<Token> rest, Rc<Vec<Rc<Stmt>>> body, bool is_generator",
+            "Match : Token keyword, Rc<Expr> subject, Rc<Vec<Rc<Pattern>>> patterns, Rc<Vec<Option<Rc<Expr>>>> guards, Rc<Vec<Rc<Stmt>>> bodies",
             "If : Rc<Expr> condition, Rc<Stmt> then_branch, Option<Rc<Stmt>> else_branch",
             "Print : Rc<Expr> expression",
             "Return : Token keyword, Option<Rc<Expr>> value",
-            "Var : Token name, Option<Rc<Expr>> initializer",
+            "Trait : Token name, Rc<Vec<Rc<Stmt>>> methods, Rc<Vec<Token>> abstract_methods",
+            "Var : Token name, Option<Rc<Expr>> initializer, bool is_const",
             "While : Rc<Expr> condition, Rc<Stmt> body",
+            "Yield : Token keyword, Option<Rc<Expr>> value",
+        ],
+    )?;
+
+    define_ast(
+        output_dir,
+        "Pattern",
+        &["error", "expr", "object", "token", "rc"],
+        &[
+            "Alternative : Token bar, Vec<Rc<Pattern>> alternatives",
+            "Binding : Token name",
+            "Class : Rc<Expr> path, Token name, Vec<Rc<Pattern>> arguments, Vec<Token> fields, Vec<Rc<Pattern>> field_patterns",
+            "List : Token bracket, Vec<Rc<Pattern>> elements, Vec<Option<Rc<Expr>>> defaults, Option<Rc<Pattern>> rest",
+            "Literal : Token token, Object value",
+            "Record : Token brace, Vec<Token> fields, Vec<Rc<Pattern>> patterns, Vec<Option<Rc<Expr>>> defaults",
+            "Value : Rc<Expr> path, Token name",
+            "Wildcard : Token token",
         ],
     )?;
 
@@ -65,7 +94,7 @@ fn define_ast(
             writeln!(file, "use std::rc::Rc;")?;
             continue;
         }
-        write!(file, "use crate::{}::*;\n", i)?;
+        writeln!(file, "use crate::{}::*;", i)?;
     }
 
     for ttype in types {
@@ -102,7 +131,7 @@ fn define_ast(
             base_name, t.base_class_name
         )?;
     }
-    write!(file, "              _ => false, \n")?;
+    writeln!(file, "              _ => false, ")?;
     writeln!(file, "        }}")?;
     writeln!(file, "    }}")?;
     writeln!(file, "}}\n\nimpl Eq for {} {{}}\n", base_name)?;
@@ -143,9 +172,9 @@ fn define_ast(
     writeln!(file, "}}\n")?;
 
     for t in &tree_types {
-        write!(file, "pub struct {} {{\n", t.class_name)?;
+        writeln!(file, "pub struct {} {{", t.class_name)?;
         for f in &t.fields {
-            write!(file, "    pub {},\n", f)?;
+            writeln!(file, "    pub {},", f)?;
         }
         write!(file, "}}\n\n")?;
     }
@@ -1,39 +1,122 @@
 use crate::interpreter::*;
 use crate::lox_class::*;
 use crate::object::*;
+use crate::token::*;
 use crate::LoxResult;
-use std::fmt::{self, Debug, Display};
 use std::rc::Rc;
 
-#[derive(Clone)]
-pub struct Callable {
-    pub func: Rc<dyn LoxCallable>,
-}
+pub trait LoxCallable {
+    fn call(
+        &self,
+        interpreter: &Interpreter,
+        arguments: Vec<Object>,
+        klass: Option<Rc<LoxClass>>,
+    ) -> Result<Object, LoxResult>;
+    fn arity(&self) -> usize;
 
-impl Debug for Callable {
-    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f, "<Callable>")
+    // Calls with the arguments of a call site, which may include named ones
+    fn call_with(
+        &self,
+        interpreter: &Interpreter,
+        arguments: CallArguments,
+        klass: Option<Rc<LoxClass>>,
+    ) -> Result<Object, LoxResult> {
+        if let Some((name, _)) = arguments.named.first() {
+            return Err(LoxResult::native_error(&format!(
+                "Unexpected named argument '{}'.",
+                name.as_string()
+            )));
+        }
+        if arguments.positional.len() != self.arity() {
+            return Err(LoxResult::native_error(&format!(
+                "Expected {} arguments but got {}.",
+                self.arity(),
+                arguments.positional.len()
+            )));
+        }
+        self.call(interpreter, arguments.positional, klass)
     }
 }
 
-impl PartialEq for Callable {
-    fn eq(&self, _other: &Self) -> bool {
-        false
-    }
+// The arguments of a call as written, before they are matched to parameters
+pub struct CallArguments {
+    pub positional: Vec<Object>,
+    pub named: Vec<(Token, Object)>,
 }
 
-impl Display for Callable {
-    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f, "<Callable>")
+impl CallArguments {
+    pub fn positional(arguments: Vec<Object>) -> Self {
+        Self {
+            positional: arguments,
+            named: Vec::new(),
+        }
     }
 }
 
-pub trait LoxCallable {
-    fn call(
-        &self,
-        interpreter: &Interpreter,
-        arguments: Vec<Object>,
-        klass: Option<Rc<LoxClass>>,
-    ) -> Result<Object, LoxResult>;
-    fn arity(&self) -> usize;
+// Arguments matched to parameters. A parameter that got no argument is None,
+// and takes its default value when the call runs.
+pub struct BoundArguments {
+    pub values: Vec<Option<Object>>,
+    pub rest: Vec<Object>,
+}
+
+// Matches arguments to `params`, of which the last `optional` have default
+// values. Extra positional arguments are only allowed with a rest parameter.
+pub fn bind_arguments(
+    callee: &str,
+    params: &[String],
+    optional: usize,
+    has_rest: bool,
+    arguments: CallArguments,
+) -> Result<BoundArguments, LoxResult> {
+    let given = arguments.positional.len();
+    let mut positional = arguments.positional.into_iter();
+    let mut values = params
+        .iter()
+        .map(|_| positional.next())
+        .collect::<Vec<Option<Object>>>();
+    let rest = positional.collect::<Vec<Object>>();
+
+    if !rest.is_empty() && !has_rest {
+        let limit = if optional == 0 { "" } else { "at most " };
+        return Err(LoxResult::native_error(&format!(
+            "'{callee}' takes {limit}{} arguments but got {given}.",
+            params.len()
+        )));
+    }
+
+    for (name, value) in arguments.named {
+        let Some(position) = params.iter().position(|p| *p == name.as_string()) else {
+            return Err(LoxResult::native_error(&format!(
+                "'{callee}' has no parameter named '{}'.",
+                name.as_string()
+            )));
+        };
+        if values[position].is_some() {
+            return Err(LoxResult::native_error(&format!(
+                "'{callee}' got more than one value for parameter '{}'.",
+                name.as_string()
+            )));
+        }
+        values[position] = Some(value);
+    }
+
+    let missing = params
+        .iter()
+        .zip(values.iter())
+        .take(params.len() - optional)
+        .filter(|(_, value)| value.is_none())
+        .map(|(param, _)| format!("'{param}'"))
+        .collect::<Vec<String>>();
+    match missing.len() {
+        0 => Ok(BoundArguments { values, rest }),
+        1 => Err(LoxResult::native_error(&format!(
+            "'{callee}' is missing an argument for parameter {}.",
+            missing[0]
+        ))),
+        _ => Err(LoxResult::native_error(&format!(
+            "'{callee}' is missing arguments for parameters {}.",
+            missing.join(", ")
+        ))),
+    }
 }
@@ -1,12 +1,12 @@
 use crate::{object::*, token::Token, LoxResult};
 use std::cell::RefCell;
-use std::collections::hash_map::Entry;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::rc::Rc;
 
 #[derive(Debug)]
 pub struct Environment {
     values: HashMap<String, Object>,
+    constants: HashSet<String>,
     enclosing: Option<Rc<RefCell<Environment>>>,
 }
 
@@ -14,6 +14,7 @@ impl Environment {
     pub fn new() -> Environment {
         Environment {
             values: HashMap::new(),
+            constants: HashSet::new(),
             enclosing: None,
         }
     }
@@ -21,6 +22,7 @@ impl Environment {
     pub fn new_with_enclosing(enclosing: Rc<RefCell<Environment>>) -> Environment {
         Environment {
             values: HashMap::new(),
+            constants: HashSet::new(),
             enclosing: Some(enclosing),
         }
     }
@@ -29,6 +31,33 @@ impl Environment {
         self.values.insert(name.to_string(), value);
     }
 
+    pub fn make_constant(&mut self, name: &str) {
+        self.constants.insert(name.to_string());
+    }
+
+    // Defines a name from a declaration in the program, which can't replace a constant
+    pub fn declare(&mut self, name: &Token, value: Object) -> Result<(), LoxResult> {
+        if self.constants.contains(&name.as_string()) {
+            return Err(LoxResult::runtime_error(
+                name,
+                &format!("Can't redefine constant '{}'.", name.as_string()),
+            ));
+        }
+        self.define(&name.as_string(), value);
+        Ok(())
+    }
+
+    fn check_not_constant(&self, name: &Token) -> Result<(), LoxResult> {
+        if self.constants.contains(&name.as_string()) {
+            Err(LoxResult::runtime_error(
+                name,
+                &format!("Can't assign to constant '{}'.", name.as_string()),
+            ))
+        } else {
+            Ok(())
+        }
+    }
+
     pub fn get(&self, name: &Token) -> Result<Object, LoxResult> {
         if let Some(object) = self.values.get(&name.as_string()) {
             Ok(object.clone())
@@ -43,16 +72,17 @@ impl Environment {
     }
 
     pub fn assign(&mut self, name: &Token, value: Object) -> Result<(), LoxResult> {
-        if let Entry::Occupied(mut object) = self.values.entry(name.as_string()) {
-            object.insert(value);
+        if self.values.contains_key(&name.as_string()) {
+            self.check_not_constant(name)?;
+            self.values.insert(name.as_string(), value);
             Ok(())
         } else if let Some(enclosing) = &self.enclosing {
             enclosing.borrow_mut().assign(name, value)
         } else {
-            return Err(LoxResult::runtime_error(
+            Err(LoxResult::runtime_error(
                 name,
                 &format!("Undefined Variable '{}'.", name.as_string()),
-            ));
+            ))
         }
     }
 
@@ -75,6 +105,7 @@ impl Environment {
         value: Object,
     ) -> Result<(), LoxResult> {
         if distance == 0 {
+            self.check_not_constant(name)?;
             self.values.insert(name.as_string(), value.clone());
             Ok(())
         } else {
@@ -7,6 +7,8 @@ pub enum LoxResult {
     LoxError { line: usize, message: String },
     SystemError { message: String },
     Break,
+    // A `?.` met nil, so the rest of its chain is skipped
+    ShortCircuit,
     ReturnValue { value: Object },
 }
 
@@ -38,12 +40,12 @@ impl LoxResult {
         err
     }
 
-    pub fn system_error(message: &str) -> LoxResult {
-        let err = LoxResult::SystemError {
+    // Natives have no token to point at, so the error is reported by the
+    // interpreter at the call site instead of here.
+    pub fn native_error(message: &str) -> LoxResult {
+        LoxResult::SystemError {
             message: message.to_string(),
-        };
-        err.report("");
-        err
+        }
     }
 
     fn report(&self, loc: &str) {
@@ -62,7 +64,7 @@ impl LoxResult {
             LoxResult::SystemError { message } => {
                 eprint!("System Error: {message}")
             }
-            LoxResult::Break | LoxResult::ReturnValue { .. } => {}
+            LoxResult::Break | LoxResult::ShortCircuit | LoxResult::ReturnValue { .. } => {}
         };
     }
 
@@ -9,9 +9,16 @@ pub enum Expr {
     Call(Rc<CallExpr>),
     Get(Rc<GetExpr>),
     Grouping(Rc<GroupingExpr>),
+    Index(Rc<IndexExpr>),
+    List(Rc<ListExpr>),
     Literal(Rc<LiteralExpr>),
     Logical(Rc<LogicalExpr>),
+    Map(Rc<MapExpr>),
+    OptionalChain(Rc<OptionalChainExpr>),
+    OptionalGet(Rc<OptionalGetExpr>),
+    Range(Rc<RangeExpr>),
     Set(Rc<SetExpr>),
+    SetIndex(Rc<SetIndexExpr>),
     Super(Rc<SuperExpr>),
     This(Rc<ThisExpr>),
     Unary(Rc<UnaryExpr>),
@@ -26,9 +33,16 @@ impl PartialEq for Expr {
             (Expr::Call(a), Expr::Call(b)) => Rc::ptr_eq(a, b),
             (Expr::Get(a), Expr::Get(b)) => Rc::ptr_eq(a, b),
             (Expr::Grouping(a), Expr::Grouping(b)) => Rc::ptr_eq(a, b),
+            (Expr::Index(a), Expr::Index(b)) => Rc::ptr_eq(a, b),
+            (Expr::List(a), Expr::List(b)) => Rc::ptr_eq(a, b),
             (Expr::Literal(a), Expr::Literal(b)) => Rc::ptr_eq(a, b),
             (Expr::Logical(a), Expr::Logical(b)) => Rc::ptr_eq(a, b),
+            (Expr::Map(a), Expr::Map(b)) => Rc::ptr_eq(a, b),
+            (Expr::OptionalChain(a), Expr::OptionalChain(b)) => Rc::ptr_eq(a, b),
+            (Expr::OptionalGet(a), Expr::OptionalGet(b)) => Rc::ptr_eq(a, b),
+            (Expr::Range(a), Expr::Range(b)) => Rc::ptr_eq(a, b),
             (Expr::Set(a), Expr::Set(b)) => Rc::ptr_eq(a, b),
+            (Expr::SetIndex(a), Expr::SetIndex(b)) => Rc::ptr_eq(a, b),
             (Expr::Super(a), Expr::Super(b)) => Rc::ptr_eq(a, b),
             (Expr::This(a), Expr::This(b)) => Rc::ptr_eq(a, b),
             (Expr::Unary(a), Expr::Unary(b)) => Rc::ptr_eq(a, b),
@@ -62,15 +76,36 @@ impl Hash for Expr {
             Expr::Grouping(a) => {
                 hasher.write_usize(Rc::as_ptr(a) as usize);
             }
+            Expr::Index(a) => {
+                hasher.write_usize(Rc::as_ptr(a) as usize);
+            }
+            Expr::List(a) => {
+                hasher.write_usize(Rc::as_ptr(a) as usize);
+            }
             Expr::Literal(a) => {
                 hasher.write_usize(Rc::as_ptr(a) as usize);
             }
             Expr::Logical(a) => {
                 hasher.write_usize(Rc::as_ptr(a) as usize);
             }
+            Expr::Map(a) => {
+                hasher.write_usize(Rc::as_ptr(a) as usize);
+            }
+            Expr::OptionalChain(a) => {
+                hasher.write_usize(Rc::as_ptr(a) as usize);
+            }
+            Expr::OptionalGet(a) => {
+                hasher.write_usize(Rc::as_ptr(a) as usize);
+            }
+            Expr::Range(a) => {
+                hasher.write_usize(Rc::as_ptr(a) as usize);
+            }
             Expr::Set(a) => {
                 hasher.write_usize(Rc::as_ptr(a) as usize);
             }
+            Expr::SetIndex(a) => {
+                hasher.write_usize(Rc::as_ptr(a) as usize);
+            }
             Expr::Super(a) => {
                 hasher.write_usize(Rc::as_ptr(a) as usize);
             }
@@ -99,9 +134,16 @@ impl Expr {
             Expr::Call(v) => expr_visitor.visit_call_expr(wrapper, v),
             Expr::Get(v) => expr_visitor.visit_get_expr(wrapper, v),
             Expr::Grouping(v) => expr_visitor.visit_grouping_expr(wrapper, v),
+            Expr::Index(v) => expr_visitor.visit_index_expr(wrapper, v),
+            Expr::List(v) => expr_visitor.visit_list_expr(wrapper, v),
             Expr::Literal(v) => expr_visitor.visit_literal_expr(wrapper, v),
             Expr::Logical(v) => expr_visitor.visit_logical_expr(wrapper, v),
+            Expr::Map(v) => expr_visitor.visit_map_expr(wrapper, v),
+            Expr::OptionalChain(v) => expr_visitor.visit_optionalchain_expr(wrapper, v),
+            Expr::OptionalGet(v) => expr_visitor.visit_optionalget_expr(wrapper, v),
+            Expr::Range(v) => expr_visitor.visit_range_expr(wrapper, v),
             Expr::Set(v) => expr_visitor.visit_set_expr(wrapper, v),
+            Expr::SetIndex(v) => expr_visitor.visit_setindex_expr(wrapper, v),
             Expr::Super(v) => expr_visitor.visit_super_expr(wrapper, v),
             Expr::This(v) => expr_visitor.visit_this_expr(wrapper, v),
             Expr::Unary(v) => expr_visitor.visit_unary_expr(wrapper, v),
@@ -125,6 +167,8 @@ pub struct CallExpr {
     pub callee: Rc<Expr>,
     pub paren: Token,
     pub arguments: Vec<Rc<Expr>>,
+    pub names: Vec<Token>,
+    pub named_arguments: Vec<Rc<Expr>>,
 }
 
 pub struct GetExpr {
@@ -136,6 +180,16 @@ pub struct GroupingExpr {
     pub expression: Rc<Expr>,
 }
 
+pub struct IndexExpr {
+    pub object: Rc<Expr>,
+    pub bracket: Token,
+    pub index: Rc<Expr>,
+}
+
+pub struct ListExpr {
+    pub elements: Vec<Rc<Expr>>,
+}
+
 pub struct LiteralExpr {
     pub value: Option<Object>,
 }
@@ -146,12 +200,41 @@ pub struct LogicalExpr {
     pub right: Rc<Expr>,
 }
 
+pub struct MapExpr {
+    pub brace: Token,
+    pub keys: Vec<Rc<Expr>>,
+    pub values: Vec<Rc<Expr>>,
+}
+
+pub struct OptionalChainExpr {
+    pub chain: Rc<Expr>,
+}
+
+pub struct OptionalGetExpr {
+    pub object: Rc<Expr>,
+    pub name: Token,
+}
+
+pub struct RangeExpr {
+    pub start: Rc<Expr>,
+    pub operator: Token,
+    pub end: Rc<Expr>,
+    pub step: Option<Rc<Expr>>,
+}
+
 pub struct SetExpr {
     pub object: Rc<Expr>,
     pub name: Token,
     pub value: Rc<Expr>,
 }
 
+pub struct SetIndexExpr {
+    pub object: Rc<Expr>,
+    pub bracket: Token,
+    pub index: Rc<Expr>,
+    pub value: Rc<Expr>,
+}
+
 pub struct SuperExpr {
     pub keyword: Token,
     pub method: Token,
@@ -176,9 +259,24 @@ pub trait ExprVisitor<T> {
     fn visit_call_expr(&self, wrapper: Rc<Expr>, expr: &CallExpr) -> Result<T, LoxResult>;
     fn visit_get_expr(&self, wrapper: Rc<Expr>, expr: &GetExpr) -> Result<T, LoxResult>;
     fn visit_grouping_expr(&self, wrapper: Rc<Expr>, expr: &GroupingExpr) -> Result<T, LoxResult>;
+    fn visit_index_expr(&self, wrapper: Rc<Expr>, expr: &IndexExpr) -> Result<T, LoxResult>;
+    fn visit_list_expr(&self, wrapper: Rc<Expr>, expr: &ListExpr) -> Result<T, LoxResult>;
     fn visit_literal_expr(&self, wrapper: Rc<Expr>, expr: &LiteralExpr) -> Result<T, LoxResult>;
     fn visit_logical_expr(&self, wrapper: Rc<Expr>, expr: &LogicalExpr) -> Result<T, LoxResult>;
+    fn visit_map_expr(&self, wrapper: Rc<Expr>, expr: &MapExpr) -> Result<T, LoxResult>;
+    fn visit_optionalchain_expr(
+        &self,
+        wrapper: Rc<Expr>,
+        expr: &OptionalChainExpr,
+    ) -> Result<T, LoxResult>;
+    fn visit_optionalget_expr(
+        &self,
+        wrapper: Rc<Expr>,
+        expr: &OptionalGetExpr,
+    ) -> Result<T, LoxResult>;
+    fn visit_range_expr(&self, wrapper: Rc<Expr>, expr: &RangeExpr) -> Result<T, LoxResult>;
     fn visit_set_expr(&self, wrapper: Rc<Expr>, expr: &SetExpr) -> Result<T, LoxResult>;
+    fn visit_setindex_expr(&self, wrapper: Rc<Expr>, expr: &SetIndexExpr) -> Result<T, LoxResult>;
     fn visit_super_expr(&self, wrapper: Rc<Expr>, expr: &SuperExpr) -> Result<T, LoxResult>;
     fn visit_this_expr(&self, wrapper: Rc<Expr>, expr: &ThisExpr) -> Result<T, LoxResult>;
     fn visit_unary_expr(&self, wrapper: Rc<Expr>, expr: &UnaryExpr) -> Result<T, LoxResult>;
@@ -3,26 +3,53 @@ use crate::environment::Environment;
 use crate::error::*;
 use crate::expr::*;
 use crate::lox_class::*;
+use crate::lox_enum::*;
 use crate::lox_function::*;
+use crate::lox_instance::*;
+use crate::lox_iterator::*;
+use crate::lox_list::*;
+use crate::lox_map::*;
+use crate::lox_range::*;
+use crate::lox_trait::*;
+use crate::native_datetime::*;
 use crate::native_functions::*;
+use crate::native_json::*;
+use crate::native_math::*;
+use crate::native_methods::*;
+use crate::native_regex::*;
 use crate::object::*;
+use crate::pattern::*;
+use crate::pattern_matcher::*;
+use crate::random::*;
 use crate::stmt::*;
 use crate::token::*;
 use crate::token_type::*;
 use core::panic;
-use std::cell::RefCell;
+use std::cell::{RefCell, RefMut};
+use std::collections::hash_map::Entry;
 use std::collections::HashMap;
 use std::ops::Deref;
 use std::rc::Rc;
 
+// The body of the selected case, and the environment it runs in
+pub type MatchedCase = (Rc<Stmt>, Rc<RefCell<Environment>>);
+
 pub struct Interpreter {
     pub globals: Rc<RefCell<Environment>>,
     environment: RefCell<Rc<RefCell<Environment>>>,
     locals: RefCell<HashMap<Rc<Expr>, usize>>,
+    privates: RefCell<HashMap<Rc<Expr>, String>>,
+    // The key of each class and trait declaration that private members are
+    // stored under, which differs even between classes with the same name
+    owners: RefCell<HashMap<Rc<Stmt>, String>>,
+    // Scope depths of the names assigned by destructuring assignments
+    bindings: RefCell<HashMap<Rc<Pattern>, usize>>,
+    random: RefCell<Random>,
+    script_args: RefCell<Vec<String>>,
 }
 
 impl StmtVisitor<()> for Interpreter {
-    fn visit_class_stmt(&self, _: Rc<Stmt>, stmt: &ClassStmt) -> Result<(), LoxResult> {
+    fn visit_class_stmt(&self, wrapper: Rc<Stmt>, stmt: &ClassStmt) -> Result<(), LoxResult> {
         let superclass = if let Some(superclass_expr) = &stmt.superclass {
             let superclass = self.evaluate(superclass_expr.clone())?;
 
@@ -40,10 +67,26 @@ impl StmtVisitor<()> for Interpreter {
             None
         };
 
+        let mut traits = Vec::new();
+        for trait_expr in stmt.traits.iter() {
+            match self.evaluate(trait_expr.clone())? {
+                Object::Trait(t) => traits.push(t),
+                other => {
+                    return Err(LoxResult::runtime_error(
+                        &stmt.name,
+                        &format!(
+                            "Class can only use traits, but got a value of type '{}'.",
+                            other.type_name()
+                        ),
+                    ))
+                }
+            }
+        }
+
         self.environment
             .borrow()
             .borrow_mut()
-            .define(&stmt.name.as_string(), Object::Nil);
+            .declare(&stmt.name, Object::Nil)?;
 
         let enclosing = if let Some(ref s) = superclass {
             let mut e = Environment::new_with_enclosing(self.environment.borrow().clone());
@@ -53,6 +96,7 @@ impl StmtVisitor<()> for Interpreter {
             None
         };
 
+        let owner = self.owner_key(&wrapper);
         let mut methods = HashMap::new();
         for method in stmt.methods.deref() {
             if let Stmt::Function(func) = method.deref() {
@@ -62,21 +106,55 @@ impl StmtVisitor<()> for Interpreter {
                     &self.environment.borrow(),
                     is_init,
                 )));
-                methods.insert(func.name.as_string(), function);
+                methods.insert(Self::method_key(&func.name, &owner), function);
             } else {
                 panic!("Non-function method in class");
             }
         }
+
+        if let Some(previous) = enclosing {
+            self.environment.replace(previous);
+        }
+
+        let mut abstract_methods = stmt
+            .abstract_methods
+            .iter()
+            .map(|m| m.as_string())
+            .collect::<Vec<String>>();
+
+        // Methods defined in the class itself win over trait methods, but two
+        // traits providing the same method is ambiguous
+        let mut origins: HashMap<String, String> = HashMap::new();
+        for t in &traits {
+            abstract_methods.extend_from_slice(t.abstract_methods());
+            for (name, method) in t.methods() {
+                if let Some(other) = origins.get(&name) {
+                    return Err(LoxResult::runtime_error(
+                        &stmt.name,
+                        &format!(
+                            "Method '{name}' is provided by both trait '{other}' and trait '{}'; class '{}' must define it to resolve the conflict.",
+                            t.name(),
+                            stmt.name.as_string()
+                        ),
+                    ));
+                }
+                if let Entry::Vacant(entry) = methods.entry(name) {
+                    origins.insert(entry.key().clone(), t.name().to_string());
+                    entry.insert(method);
+                }
+            }
+        }
+
         let klass = Object::Class(Rc::new(LoxClass::new(
             &stmt.name.as_string(),
+            stmt.fields
+                .as_ref()
+                .map(|fields| fields.iter().map(|f| f.as_string()).collect()),
             superclass,
             methods,
+            abstract_methods,
         )));
 
-        if let Some(previous) = enclosing {
-            self.environment.replace(previous);
-        }
-
         self.environment
             .borrow()
             .borrow_mut()
@@ -85,6 +163,55 @@ impl StmtVisitor<()> for Interpreter {
         Ok(())
     }
 
+    fn visit_trait_stmt(&self, wrapper: Rc<Stmt>, stmt: &TraitStmt) -> Result<(), LoxResult> {
+        let owner = self.owner_key(&wrapper);
+        let mut methods = HashMap::new();
+        for method in stmt.methods.deref() {
+            if let Stmt::Function(func) = method.deref() {
+                let is_init = func.name.as_string() == "init";
+                let function = Object::Func(Rc::new(LoxFunction::new(
+                    func,
+                    &self.environment.borrow(),
+                    is_init,
+                )));
+                methods.insert(Self::method_key(&func.name, &owner), function);
+            } else {
+                panic!("Non-function method in trait");
+            }
+        }
+
+        self.environment.borrow().borrow_mut().declare(
+            &stmt.name,
+            Object::Trait(Rc::new(LoxTrait::new(
+                &stmt.name.as_string(),
+                methods,
+                stmt.abstract_methods
+                    .iter()
+                    .map(|m| m.as_string())
+                    .collect(),
+            ))),
+        )
+    }
+
+    fn visit_enum_stmt(&self, _: Rc<Stmt>, stmt: &EnumStmt) -> Result<(), LoxResult> {
+        let variants = stmt
+            .variants
+            .iter()
+            .zip(stmt.payloads.iter())
+            .map(|(name, payload)| EnumVariant {
+                name: name.as_string(),
+                fields: payload
+                    .as_ref()
+                    .map(|fields| fields.iter().map(|f| f.as_string()).collect()),
+            })
+            .collect();
+
+        self.environment.borrow().borrow_mut().declare(
+            &stmt.name,
+            Object::Enum(Rc::new(LoxEnum::new(&stmt.name.as_string(), variants))),
+        )
+    }
+
     fn visit_return_stmt(&self, _: Rc<Stmt>, stmt: &ReturnStmt) -> Result<(), LoxResult> {
         if let Some(value) = stmt.value.clone() {
             Err(LoxResult::return_value(self.evaluate(value)?))
@@ -98,8 +225,7 @@ impl StmtVisitor<()> for Interpreter {
         self.environment
             .borrow()
             .borrow_mut()
-            .define(&stmt.name.as_string(), Object::Func(Rc::new(function)));
-        Ok(())
+            .declare(&stmt.name, Object::Func(Rc::new(function)))
     }
 
     fn visit_break_stmt(&self, _: Rc<Stmt>, _stmt: &BreakStmt) -> Result<(), LoxResult> {
@@ -115,6 +241,31 @@ impl StmtVisitor<()> for Interpreter {
         }
         Ok(())
     }
+    fn visit_forin_stmt(&self, _: Rc<Stmt>, stmt: &ForInStmt) -> Result<(), LoxResult> {
+        let iterable = self.evaluate(stmt.iterable.clone())?;
+        let mut iterator = LoxIterator::new(self, iterable, &stmt.name)?;
+        let body = Rc::new(vec![stmt.body.clone()]);
+
+        // Every iteration gets a fresh environment so closures capture that iteration's value
+        let result = loop {
+            let value = match iterator.next(self, &stmt.name) {
+                Ok(Some(value)) => value,
+                Ok(None) => break Ok(()),
+                Err(e) => break Err(e),
+            };
+            let mut e = Environment::new_with_enclosing(self.environment.borrow().clone());
+            e.define(&stmt.name.as_string(), value);
+            match self.execute_block(&body, Rc::new(RefCell::new(e))) {
+                Err(LoxResult::Break) => break Ok(()),
+                Err(e) => break Err(e),
+                Ok(_) => {}
+            }
+        };
+
+        iterator.close();
+        result
+    }
+
     fn visit_if_stmt(&self, _: Rc<Stmt>, stmt: &IfStmt) -> Result<(), LoxResult> {
         if self.is_truthy(&self.evaluate(stmt.condition.clone())?) {
             self.execute(stmt.then_branch.clone())
@@ -128,7 +279,7 @@ impl StmtVisitor<()> for Interpreter {
     fn visit_block_stmt(&self, _: Rc<Stmt>, stmt: &BlockStmt) -> Result<(), LoxResult> {
         let e = Environment::new_with_enclosing(self.environment.borrow().clone());
 
-        self.execute_block(&stmt.statements, e)
+        self.execute_block(&stmt.statements, Rc::new(RefCell::new(e)))
     }
 
     fn visit_expression_stmt(&self, _: Rc<Stmt>, stmt: &ExpressionStmt) -> Result<(), LoxResult> {
@@ -136,12 +287,51 @@ impl StmtVisitor<()> for Interpreter {
         Ok(())
     }
 
+    fn visit_match_stmt(&self, _: Rc<Stmt>, stmt: &MatchStmt) -> Result<(), LoxResult> {
+        let environment = self.environment.borrow().clone();
+        if let Some((body, environment)) = self.select_case(stmt, environment)? {
+            self.execute_in(body, environment)?;
+        }
+        Ok(())
+    }
+
     fn visit_print_stmt(&self, _: Rc<Stmt>, stmt: &PrintStmt) -> Result<(), LoxResult> {
         let value = self.evaluate(stmt.expression.clone())?;
         println!("{value}");
         Ok(())
     }
 
+    fn visit_yield_stmt(&self, _: Rc<Stmt>, stmt: &YieldStmt) -> Result<(), LoxResult> {
+        // Generator bodies handle yield themselves, see LoxGenerator
+        Err(LoxResult::runtime_error(
+            &stmt.keyword,
+            "Can't yield outside of a generator function.",
+        ))
+    }
+
+    fn visit_destructure_stmt(&self, _: Rc<Stmt>, stmt: &DestructureStmt) -> Result<(), LoxResult> {
+        let value = self.evaluate(stmt.value.clone())?;
+        let mode = match &stmt.keyword {
+            Some(_) => BindingMode::Declare,
+            None => BindingMode::Assign,
+        };
+        let environment = self.environment.borrow().clone();
+        PatternMatcher::with_mode(self, Rc::clone(&environment), mode).destructure(
+            &stmt.pattern,
+            value,
+            &stmt.equals,
+        )?;
+
+        if let Some(keyword) = &stmt.keyword {
+            if keyword.is(TokenType::Const) {
+                for name in pattern_bindings(&stmt.pattern) {
+                    environment.borrow_mut().make_constant(&name.as_string());
+                }
+            }
+        }
+        Ok(())
+    }
+
     fn visit_var_stmt(&self, _: Rc<Stmt>, stmt: &VarStmt) -> Result<(), LoxResult> {
         let value = if let Some(initializer) = stmt.initializer.clone() {
             self.evaluate(initializer)?
@@ -149,10 +339,12 @@ impl StmtVisitor<()> for Interpreter {
             Object::Nil
         };
 
-        self.environment
-            .borrow()
-            .borrow_mut()
-            .define(&stmt.name.as_string(), value);
+        let environment = self.environment.borrow();
+        let mut environment = environment.borrow_mut();
+        environment.declare(&stmt.name, value)?;
+        if stmt.is_const {
+            environment.make_constant(&stmt.name.as_string());
+        }
         Ok(())
     }
 }
@@ -160,12 +352,7 @@ impl StmtVisitor<()> for Interpreter {
 impl ExprVisitor<Object> for Interpreter {
     fn visit_super_expr(&self, wrapper: Rc<Expr>, expr: &SuperExpr) -> Result<Object, LoxResult> {
         let distance = *self.locals.borrow().get(&wrapper).unwrap();
-        let superclass = if let Some(s) = self
-            .environment
-            .borrow()
-            .borrow()
-            .get_at(distance, "super")
-            .ok()
+        let superclass = if let Ok(s) = self.environment.borrow().borrow().get_at(distance, "super")
         {
             if let Object::Class(super_class) = s {
                 super_class
@@ -185,15 +372,15 @@ impl ExprVisitor<Object> for Interpreter {
             .unwrap();
         if let Some(method) = superclass.find_method(&expr.method.as_string()) {
             if let Object::Func(func) = method {
-                return Ok(func.bind(&object));
+                Ok(func.bind(&object))
             } else {
                 panic!("method was not a function")
             }
         } else {
-            return Err(LoxResult::runtime_error(
+            Err(LoxResult::runtime_error(
                 &expr.method,
                 &format!("Undefined property {}.", expr.method.as_string()),
-            ));
+            ))
         }
     }
 
@@ -201,11 +388,12 @@ impl ExprVisitor<Object> for Interpreter {
         self.look_up_variable(&expr.keyword, wrapper)
     }
 
-    fn visit_set_expr(&self, _: Rc<Expr>, expr: &SetExpr) -> Result<Object, LoxResult> {
+    fn visit_set_expr(&self, wrapper: Rc<Expr>, expr: &SetExpr) -> Result<Object, LoxResult> {
         let object = self.evaluate(expr.object.clone())?;
         if let Object::Instance(inst) = object {
+            let key = self.member_key(&wrapper, &expr.name)?;
             let value = self.evaluate(expr.value.clone())?;
-            inst.set(&expr.name, value.clone());
+            inst.set(&key, value.clone());
             Ok(value)
         } else {
             Err(LoxResult::runtime_error(
@@ -215,23 +403,167 @@ impl ExprVisitor<Object> for Interpreter {
         }
     }
 
-    fn visit_get_expr(&self, _: Rc<Expr>, expr: &GetExpr) -> Result<Object, LoxResult> {
+    fn visit_get_expr(&self, wrapper: Rc<Expr>, expr: &GetExpr) -> Result<Object, LoxResult> {
         let object = self.evaluate(expr.object.clone())?;
-        if let Object::Instance(inst) = object {
-            Ok(inst.get(&expr.name, &inst)?)
-        } else {
-            Err(LoxResult::runtime_error(
-                &expr.name,
-                "Only instances have properties",
-            ))
+        self.get_property(&wrapper, object, &expr.name)
+    }
+
+    fn visit_optionalget_expr(
+        &self,
+        wrapper: Rc<Expr>,
+        expr: &OptionalGetExpr,
+    ) -> Result<Object, LoxResult> {
+        match self.evaluate(expr.object.clone())? {
+            Object::Nil => Err(LoxResult::ShortCircuit),
+            object => self.get_property(&wrapper, object, &expr.name),
+        }
+    }
+
+    fn visit_optionalchain_expr(
+        &self,
+        _: Rc<Expr>,
+        expr: &OptionalChainExpr,
+    ) -> Result<Object, LoxResult> {
+        match self.evaluate(expr.chain.clone()) {
+            Err(LoxResult::ShortCircuit) => Ok(Object::Nil),
+            result => result,
+        }
+    }
+
+    fn visit_index_expr(&self, _: Rc<Expr>, expr: &IndexExpr) -> Result<Object, LoxResult> {
+        let object = self.evaluate(expr.object.clone())?;
+        let index = self.evaluate(expr.index.clone())?;
+
+        match (&object, &index) {
+            (Object::List(list), Object::Range(range)) => {
+                let list = list.borrow();
+                let positions = slice_positions(range, list.len())
+                    .map_err(|e| LoxResult::runtime_error(&expr.bracket, &e))?;
+                let slice = positions.into_iter().map(|i| list[i].clone()).collect();
+                return Ok(Object::List(Rc::new(LoxList::new(slice))));
+            }
+            (Object::Str(s), Object::Range(range)) => {
+                let chars = s.chars().collect::<Vec<char>>();
+                let positions = slice_positions(range, chars.len())
+                    .map_err(|e| LoxResult::runtime_error(&expr.bracket, &e))?;
+                return Ok(Object::Str(
+                    positions.into_iter().map(|i| chars[i]).collect(),
+                ));
+            }
+            _ => {}
+        }
+
+        match &object {
+            Object::List(list) => {
+                let list = list.borrow();
+                let position = as_index(&index, list.len())
+                    .map_err(|e| LoxResult::runtime_error(&expr.bracket, &e))?;
+                return Ok(list[position].clone());
+            }
+            Object::Str(s) => {
+                let position = as_index(&index, s.chars().count())
+                    .map_err(|e| LoxResult::runtime_error(&expr.bracket, &e))?;
+                return Ok(Object::Str(s.chars().nth(position).unwrap().to_string()));
+            }
+            Object::Map(map) => {
+                return map.get(&index).ok_or_else(|| {
+                    LoxResult::runtime_error(
+                        &expr.bracket,
+                        &format!("Key {} not found in map.", index.repr()),
+                    )
+                });
+            }
+            Object::Instance(inst) => {
+                if let Some(result) =
+                    self.call_operator_method(inst, "__index__", vec![index], &expr.bracket)
+                {
+                    return result;
+                }
+            }
+            _ => {}
+        }
+
+        Err(self.unsupported_index(&object, "__index__", &expr.bracket))
+    }
+
+    fn visit_range_expr(&self, _: Rc<Expr>, expr: &RangeExpr) -> Result<Object, LoxResult> {
+        let start = self.evaluate(expr.start.clone())?;
+        let end = self.evaluate(expr.end.clone())?;
+        let step = match &expr.step {
+            Some(step) => self.evaluate(step.clone())?,
+            None => Object::Num(1.0),
+        };
+
+        match (start, end, step) {
+            (Object::Num(_), Object::Num(_), Object::Num(0.0)) => Err(LoxResult::runtime_error(
+                &expr.operator,
+                "Range step can't be zero.",
+            )),
+            (Object::Num(start), Object::Num(end), Object::Num(step)) => {
+                let inclusive = expr.operator.is(TokenType::DotDotEqual);
+                Ok(Object::Range(Rc::new(LoxRange::new(
+                    start, end, step, inclusive,
+                ))))
+            }
+            _ => Err(LoxResult::runtime_error(
+                &expr.operator,
+                "Range bounds and step must be numbers.",
+            )),
         }
     }
 
+    fn visit_setindex_expr(&self, _: Rc<Expr>, expr: &SetIndexExpr) -> Result<Object, LoxResult> {
+        let object = self.evaluate(expr.object.clone())?;
+        let index = self.evaluate(expr.index.clone())?;
+        let value = self.evaluate(expr.value.clone())?;
+
+        match &object {
+            Object::List(list) => {
+                let mut list = list.borrow_mut();
+                let position = as_index(&index, list.len())
+                    .map_err(|e| LoxResult::runtime_error(&expr.bracket, &e))?;
+                list[position] = value.clone();
+                return Ok(value);
+            }
+            Object::Map(map) => {
+                if !map.insert(index.clone(), value.clone()) {
+                    return Err(LoxResult::runtime_error(
+                        &expr.bracket,
+                        &format!(
+                            "Can't use a value of type '{}' as a map key.",
+                            index.type_name()
+                        ),
+                    ));
+                }
+                return Ok(value);
+            }
+            Object::Instance(inst) => {
+                if let Some(result) = self.call_operator_method(
+                    inst,
+                    "__setindex__",
+                    vec![index, value.clone()],
+                    &expr.bracket,
+                ) {
+                    result?;
+                    return Ok(value);
+                }
+            }
+            _ => {}
+        }
+
+        Err(self.unsupported_index(&object, "__setindex__", &expr.bracket))
+    }
+
     fn visit_call_expr(&self, _: Rc<Expr>, expr: &CallExpr) -> Result<Object, LoxResult> {
         let callee = self.evaluate(expr.callee.clone())?;
-        let mut arguments = Vec::new();
+        let mut arguments = CallArguments::positional(Vec::new());
         for argument in &expr.arguments {
-            arguments.push(self.evaluate(argument.clone())?);
+            arguments.positional.push(self.evaluate(argument.clone())?);
+        }
+        for (name, argument) in expr.names.iter().zip(expr.named_arguments.iter()) {
+            arguments
+                .named
+                .push((name.dup(), self.evaluate(argument.clone())?));
         }
 
         let (call_func, klass): (Option<Rc<dyn LoxCallable>>, Option<Rc<LoxClass>>) = match callee {
@@ -245,17 +577,12 @@ impl ExprVisitor<Object> for Interpreter {
         };
 
         if let Some(func) = call_func {
-            if arguments.len() != func.arity() {
-                return Err(LoxResult::runtime_error(
-                    &expr.paren,
-                    &format!(
-                        "Expected {} arguments but got {}.",
-                        func.arity(),
-                        arguments.len()
-                    ),
-                ));
-            }
-            func.call(self, arguments, klass)
+            func.call_with(self, arguments, klass).map_err(|e| match e {
+                LoxResult::SystemError { message } => {
+                    LoxResult::runtime_error(&expr.paren, &message)
+                }
+                e => e,
+            })
         } else {
             Err(LoxResult::runtime_error(
                 &expr.paren,
@@ -267,7 +594,11 @@ impl ExprVisitor<Object> for Interpreter {
     fn visit_logical_expr(&self, _: Rc<Expr>, expr: &LogicalExpr) -> Result<Object, LoxResult> {
         let left = self.evaluate(expr.left.clone())?;
 
-        if expr.operator.is(TokenType::Or) {
+        if expr.operator.is(TokenType::QuestionQuestion) {
+            if left != Object::Nil {
+                return Ok(left);
+            }
+        } else if expr.operator.is(TokenType::Or) {
             if self.is_truthy(&left) {
                 return Ok(left);
             }
@@ -302,67 +633,39 @@ impl ExprVisitor<Object> for Interpreter {
         Ok(expr.value.clone().unwrap())
     }
 
+    fn visit_list_expr(&self, _: Rc<Expr>, expr: &ListExpr) -> Result<Object, LoxResult> {
+        let mut elements = Vec::new();
+        for element in &expr.elements {
+            elements.push(self.evaluate(element.clone())?);
+        }
+        Ok(Object::List(Rc::new(LoxList::new(elements))))
+    }
+
+    fn visit_map_expr(&self, _: Rc<Expr>, expr: &MapExpr) -> Result<Object, LoxResult> {
+        let map = LoxMap::new();
+        for (key, value) in expr.keys.iter().zip(expr.values.iter()) {
+            let key = self.evaluate(key.clone())?;
+            let value = self.evaluate(value.clone())?;
+            if !LoxMap::is_valid_key(&key) {
+                return Err(LoxResult::runtime_error(
+                    &expr.brace,
+                    &format!(
+                        "Can't use a value of type '{}' as a map key.",
+                        key.type_name()
+                    ),
+                ));
+            }
+            map.insert(key, value);
+        }
+        Ok(Object::Map(Rc::new(map)))
+    }
+
     fn visit_binary_expr(&self, _: Rc<Expr>, expr: &BinaryExpr) -> Result<Object, LoxResult> {
         let left = self.evaluate(expr.left.clone())?;
         let right = self.evaluate(expr.right.clone())?;
-        let op = expr.operator.token_type();
-
-        let result = match (left, right) {
-            (Object::Num(left), Object::Num(right)) => match op {
-                TokenType::Minus => Object::Num(left - right),
-                TokenType::Slash => Object::Num(left / right),
-                TokenType::Star => Object::Num(left * right),
-                TokenType::Plus => Object::Num(left + right),
-                TokenType::Greater => Object::Bool(left > right),
-                TokenType::GreaterEqual => Object::Bool(left >= right),
-                TokenType::Less => Object::Bool(left < right),
-                TokenType::LessEqual => Object::Bool(left <= right),
-                TokenType::BangEqual => Object::Bool(left != right),
-                TokenType::Equals => Object::Bool(left == right),
-                _ => Object::ArithmeticError,
-            },
-            (Object::Str(left), Object::Num(right)) => match op {
-                TokenType::Plus => Object::Str(format!("{left}{right}")),
-                _ => Object::ArithmeticError,
-            },
-            (Object::Num(left), Object::Str(right)) => match op {
-                TokenType::Plus => Object::Str(format!("{left}{right}")),
-                _ => Object::ArithmeticError,
-            },
-            (Object::Str(left), Object::Str(right)) => match op {
-                TokenType::Plus => Object::Str(format!("{left}{right}")),
-                TokenType::BangEqual => Object::Bool(left != right),
-                TokenType::Equals => Object::Bool(left == right),
-                _ => Object::ArithmeticError,
-            },
-
-            (Object::Bool(left), Object::Bool(right)) => match op {
-                TokenType::BangEqual => Object::Bool(left != right),
-                TokenType::Equals => Object::Bool(left == right),
-                _ => Object::ArithmeticError,
-            },
-            (Object::Nil, Object::Nil) => match op {
-                TokenType::BangEqual => Object::Bool(false),
-                TokenType::Equals => Object::Bool(true),
-                _ => Object::ArithmeticError,
-            },
-            (Object::Nil, _) => match op {
-                TokenType::BangEqual => Object::Bool(true),
-                TokenType::Equals => Object::Bool(false),
-                _ => Object::ArithmeticError,
-            },
-            _ => Object::ArithmeticError,
-        };
-
-        if result == Object::ArithmeticError {
-            Err(LoxResult::runtime_error(
-                &expr.operator,
-                "Illegal expression",
-            ))
-        } else {
-            Ok(result)
-        }
+        self.binary(&expr.operator, left, right)
     }
+
     fn visit_grouping_expr(&self, _: Rc<Expr>, expr: &GroupingExpr) -> Result<Object, LoxResult> {
         self.evaluate(expr.expression.clone())
     }
@@ -372,6 +675,18 @@ impl ExprVisitor<Object> for Interpreter {
         match expr.operator.token_type() {
             TokenType::Minus => match right {
                 Object::Num(n) => Ok(Object::Num(-n)),
+                Object::Instance(inst) => {
+                    match self.call_operator_method(&inst, "__neg__", Vec::new(), &expr.operator) {
+                        Some(result) => result,
+                        None => Err(LoxResult::runtime_error(
+                            &expr.operator,
+                            &format!(
+                                "Unsupported operand type for unary '-': '{}' has no '__neg__' method.",
+                                inst.class_name()
+                            ),
+                        )),
+                    }
+                }
                 _ => Ok(Object::Nil),
             },
             TokenType::Bang => Ok(Object::Bool(!self.is_truthy(&right))),
@@ -393,14 +708,21 @@ impl Interpreter {
     pub fn new() -> Interpreter {
         let globals = Rc::new(RefCell::new(Environment::new()));
 
-        globals
-            .borrow_mut()
-            .define("clock", Object::Native(Rc::new(NativeClock {})));
+        define_natives(&mut globals.borrow_mut());
+        define_namespace(&mut globals.borrow_mut(), math_namespace());
+        define_namespace(&mut globals.borrow_mut(), json_namespace());
+        define_namespace(&mut globals.borrow_mut(), datetime_namespace());
+        define_namespace(&mut globals.borrow_mut(), regex_namespace());
 
         Interpreter {
             globals: Rc::clone(&globals),
             environment: RefCell::new(Rc::clone(&globals)),
             locals: RefCell::new(HashMap::new()),
+            privates: RefCell::new(HashMap::new()),
+            owners: RefCell::new(HashMap::new()),
+            bindings: RefCell::new(HashMap::new()),
+            random: RefCell::new(Random::from_time()),
+            script_args: RefCell::new(Vec::new()),
         }
     }
     fn evaluate(&self, expr: Rc<Expr>) -> Result<Object, LoxResult> {
@@ -414,9 +736,9 @@ impl Interpreter {
     pub fn execute_block(
         &self,
         statements: &Rc<Vec<Rc<Stmt>>>,
-        environment: Environment,
+        environment: Rc<RefCell<Environment>>,
     ) -> Result<(), LoxResult> {
-        let previous = self.environment.replace(Rc::new(RefCell::new(environment)));
+        let previous = self.environment.replace(environment);
 
         let result = statements
             .iter()
@@ -427,8 +749,62 @@ impl Interpreter {
         result
     }
 
+    pub fn execute_in(
+        &self,
+        stmt: Rc<Stmt>,
+        environment: Rc<RefCell<Environment>>,
+    ) -> Result<(), LoxResult> {
+        let previous = self.environment.replace(environment);
+        let result = self.execute(stmt);
+        self.environment.replace(previous);
+        result
+    }
+
+    pub fn evaluate_in(
+        &self,
+        expr: Rc<Expr>,
+        environment: Rc<RefCell<Environment>>,
+    ) -> Result<Object, LoxResult> {
+        let previous = self.environment.replace(environment);
+        let result = self.evaluate(expr);
+        self.environment.replace(previous);
+        result
+    }
+
+    // Finds the first case whose pattern and guard accept the subject, along
+    // with an environment holding the names its pattern bound
+    pub fn select_case(
+        &self,
+        stmt: &MatchStmt,
+        environment: Rc<RefCell<Environment>>,
+    ) -> Result<Option<MatchedCase>, LoxResult> {
+        let subject = self.evaluate_in(stmt.subject.clone(), Rc::clone(&environment))?;
+        let cases = stmt
+            .patterns
+            .iter()
+            .zip(stmt.guards.iter())
+            .zip(stmt.bodies.iter());
+        for ((pattern, guard), body) in cases {
+            let bindings = Rc::new(RefCell::new(Environment::new_with_enclosing(Rc::clone(
+                &environment,
+            ))));
+            let matcher = PatternMatcher::new(self, Rc::clone(&bindings));
+            if !matcher.matches(pattern, subject.clone())? {
+                continue;
+            }
+            if let Some(guard) = guard {
+                let accepted = self.evaluate_in(guard.clone(), Rc::clone(&bindings))?;
+                if !self.is_truthy(&accepted) {
+                    continue;
+                }
+            }
+            return Ok(Some((Rc::clone(body), bindings)));
+        }
+        Ok(None)
+    }
+
     // anything that is not Nil or False is true
-    fn is_truthy(&self, object: &Object) -> bool {
+    pub fn is_truthy(&self, object: &Object) -> bool {
         !matches!(object, Object::Bool(false) | Object::Nil)
     }
 
@@ -452,6 +828,357 @@ impl Interpreter {
         self.locals.borrow_mut().insert(expr, depth);
     }
 
+    pub fn random(&self) -> RefMut<'_, Random> {
+        self.random.borrow_mut()
+    }
+
+    pub fn seed_random(&self, seed: u64) {
+        self.random.replace(Random::new(seed));
+    }
+
+    pub fn script_args(&self) -> Vec<String> {
+        self.script_args.borrow().clone()
+    }
+
+    pub fn set_script_args(&self, args: Vec<String>) {
+        self.script_args.replace(args);
+    }
+
+    pub fn resolve_binding(&self, pattern: Rc<Pattern>, depth: usize) {
+        self.bindings.borrow_mut().insert(pattern, depth);
+    }
+
+    // Assigns a name bound by a destructuring assignment, like `visit_assign_expr`
+    pub fn assign_binding(
+        &self,
+        pattern: &Rc<Pattern>,
+        name: &Token,
+        value: Object,
+        environment: &Rc<RefCell<Environment>>,
+    ) -> Result<(), LoxResult> {
+        if let Some(distance) = self.bindings.borrow().get(pattern) {
+            environment.borrow_mut().assign_at(*distance, name, value)
+        } else {
+            self.globals.borrow_mut().assign(name, value)
+        }
+    }
+
+    pub fn resolve_private(&self, expr: Rc<Expr>, owner: &str) {
+        self.privates.borrow_mut().insert(expr, owner.to_string());
+    }
+
+    // Numbers each class and trait declaration the first time it is resolved
+    pub fn declare_owner(&self, declaration: Rc<Stmt>, name: &Token) -> String {
+        let mut owners = self.owners.borrow_mut();
+        let key = format!("{}/{}", name.as_string(), owners.len());
+        owners.entry(declaration).or_insert(key).clone()
+    }
+
+    fn owner_key(&self, declaration: &Rc<Stmt>) -> String {
+        self.owners
+            .borrow()
+            .get(declaration)
+            .cloned()
+            .expect("class declarations are resolved before they run")
+    }
+
+    // Private members are stored under a key that includes the class or trait
+    // that declared them, so only code in that class can reach them
+    fn member_key(&self, expr: &Rc<Expr>, name: &Token) -> Result<String, LoxResult> {
+        if !name.is_private() {
+            Ok(name.as_string())
+        } else if let Some(owner) = self.privates.borrow().get(expr) {
+            Ok(private_key(&name.as_string(), owner))
+        } else {
+            Err(LoxResult::runtime_error(
+                name,
+                &format!(
+                    "Private member '{}' is not accessible here.",
+                    name.as_string()
+                ),
+            ))
+        }
+    }
+
+    fn get_property(
+        &self,
+        wrapper: &Rc<Expr>,
+        object: Object,
+        name: &Token,
+    ) -> Result<Object, LoxResult> {
+        match object {
+            Object::Instance(inst) => {
+                let key = self.member_key(wrapper, name)?;
+                inst.get(&key, name, &inst)
+            }
+            Object::List(list) => list_method(&list, name),
+            Object::Map(map) => map_method(&map, name),
+            Object::Str(s) => string_method(&s, name),
+            Object::Num(n) => number_method(n, name),
+            Object::Bool(b) => bool_method(b, name),
+            Object::Range(range) => range_method(&range, name),
+            Object::Generator(generator) => generator_method(&generator, name),
+            Object::Namespace(namespace) => namespace.get(name),
+            Object::Enum(lox_enum) => enum_method(&lox_enum, name),
+            Object::EnumValue(value) => enum_value_property(&value, name),
+            Object::DateTime(datetime) => datetime_method(&datetime, name),
+            Object::Duration(duration) => duration_method(&duration, name),
+            Object::Regex(regex) => regex_method(&regex, name),
+            _ => Err(LoxResult::runtime_error(
+                name,
+                "Only instances have properties",
+            )),
+        }
+    }
+
+    fn binary(&self, operator: &Token, left: Object, right: Object) -> Result<Object, LoxResult> {
+        let op = operator.token_type();
+
+        if matches!(left, Object::Instance(_)) || matches!(right, Object::Instance(_)) {
+            return self.overloaded_binary(operator, left, right);
+        }
+
+        let result = match (left, right) {
+            (Object::Num(left), Object::Num(right)) => match op {
+                TokenType::Minus => Object::Num(left - right),
+                TokenType::Slash => Object::Num(left / right),
+                TokenType::Star => Object::Num(left * right),
+                TokenType::Plus => Object::Num(left + right),
+                TokenType::Greater => Object::Bool(left > right),
+                TokenType::GreaterEqual => Object::Bool(left >= right),
+                TokenType::Less => Object::Bool(left < right),
+                TokenType::LessEqual => Object::Bool(left <= right),
+                TokenType::BangEqual => Object::Bool(left != right),
+                TokenType::Equals => Object::Bool(left == right),
+                _ => Object::ArithmeticError,
+            },
+            (Object::Str(left), Object::Num(right)) => match op {
+                TokenType::Plus => Object::Str(format!("{left}{right}")),
+                _ => Object::ArithmeticError,
+            },
+            (Object::Num(left), Object::Str(right)) => match op {
+                TokenType::Plus => Object::Str(format!("{left}{right}")),
+                _ => Object::ArithmeticError,
+            },
+            (Object::Str(left), Object::Str(right)) => match op {
+                TokenType::Plus => Object::Str(format!("{left}{right}")),
+                TokenType::BangEqual => Object::Bool(left != right),
+                TokenType::Equals => Object::Bool(left == right),
+                _ => Object::ArithmeticError,
+            },
+
+            (Object::Bool(left), Object::Bool(right)) => match op {
+                TokenType::BangEqual => Object::Bool(left != right),
+                TokenType::Equals => Object::Bool(left == right),
+                _ => Object::ArithmeticError,
+            },
+            (Object::EnumValue(left), Object::EnumValue(right)) if left.same_enum(&right) => {
+                match op {
+                    TokenType::Greater => Object::Bool(left.ordinal() > right.ordinal()),
+                    TokenType::GreaterEqual => Object::Bool(left.ordinal() >= right.ordinal()),
+                    TokenType::Less => Object::Bool(left.ordinal() < right.ordinal()),
+                    TokenType::LessEqual => Object::Bool(left.ordinal() <= right.ordinal()),
+                    TokenType::BangEqual => Object::Bool(left != right),
+                    TokenType::Equals => Object::Bool(left == right),
+                    _ => Object::ArithmeticError,
+                }
+            }
+            (Object::DateTime(left), Object::DateTime(right)) => match op {
+                TokenType::Minus => time_value(operator, left.since(&right), Object::Duration)?,
+                TokenType::Greater => Object::Bool(left > right),
+                TokenType::GreaterEqual => Object::Bool(left >= right),
+                TokenType::Less => Object::Bool(left < right),
+                TokenType::LessEqual => Object::Bool(left <= right),
+                TokenType::BangEqual => Object::Bool(left != right),
+                TokenType::Equals => Object::Bool(left == right),
+                _ => Object::ArithmeticError,
+            },
+            (Object::DateTime(left), Object::Duration(right)) => match op {
+                TokenType::Plus => time_value(operator, left.plus(&right), Object::DateTime)?,
+                TokenType::Minus => {
+                    let back = right.times(-1.0).and_then(|back| left.plus(&back));
+                    time_value(operator, back, Object::DateTime)?
+                }
+                _ => Object::ArithmeticError,
+            },
+            (Object::Duration(left), Object::DateTime(right)) => match op {
+                TokenType::Plus => time_value(operator, right.plus(&left), Object::DateTime)?,
+                _ => Object::ArithmeticError,
+            },
+            (Object::Duration(left), Object::Duration(right)) => match op {
+                TokenType::Plus => time_value(operator, left.plus(&right), Object::Duration)?,
+                TokenType::Minus => {
+                    let back = right.times(-1.0).and_then(|back| left.plus(&back));
+                    time_value(operator, back, Object::Duration)?
+                }
+                TokenType::Greater => Object::Bool(left > right),
+                TokenType::GreaterEqual => Object::Bool(left >= right),
+                TokenType::Less => Object::Bool(left < right),
+                TokenType::LessEqual => Object::Bool(left <= right),
+                TokenType::BangEqual => Object::Bool(left != right),
+                TokenType::Equals => Object::Bool(left == right),
+                _ => Object::ArithmeticError,
+            },
+            (Object::Duration(left), Object::Num(right)) => match op {
+                TokenType::Star => time_value(operator, left.times(right), Object::Duration)?,
+                TokenType::Slash if right != 0.0 => {
+                    time_value(operator, left.times(1.0 / right), Object::Duration)?
+                }
+                _ => Object::ArithmeticError,
+            },
+            (Object::Num(left), Object::Duration(right)) => match op {
+                TokenType::Star => time_value(operator, right.times(left), Object::Duration)?,
+                _ => Object::ArithmeticError,
+            },
+            (Object::Nil, Object::Nil) => match op {
+                TokenType::BangEqual => Object::Bool(false),
+                TokenType::Equals => Object::Bool(true),
+                _ => Object::ArithmeticError,
+            },
+            (Object::Nil, _) => match op {
+                TokenType::BangEqual => Object::Bool(true),
+                TokenType::Equals => Object::Bool(false),
+                _ => Object::ArithmeticError,
+            },
+            (left, right) => match op {
+                TokenType::BangEqual => Object::Bool(left != right),
+                TokenType::Equals => Object::Bool(left == right),
+                _ => Object::ArithmeticError,
+            },
+        };
+
+        if result == Object::ArithmeticError {
+            Err(LoxResult::runtime_error(operator, "Illegal expression"))
+        } else {
+            Ok(result)
+        }
+    }
+
+    // Equality for values of any type, going through `__eq__` for instances
+    pub fn values_equal(
+        &self,
+        left: &Object,
+        right: &Object,
+        token: &Token,
+    ) -> Result<bool, LoxResult> {
+        if !matches!(left, Object::Instance(_)) && !matches!(right, Object::Instance(_)) {
+            return Ok(left == right);
+        }
+        let operator = Token::new(TokenType::Equals, "==".to_string(), None, token.line);
+        let result = self.binary(&operator, left.clone(), right.clone())?;
+        Ok(self.is_truthy(&result))
+    }
+
+    // Dispatches a binary operator to the special method on the left operand,
+    // falling back to the reflected method on the right operand.
+    fn overloaded_binary(
+        &self,
+        operator: &Token,
+        left: Object,
+        right: Object,
+    ) -> Result<Object, LoxResult> {
+        let op = operator.token_type();
+        let (method, reflected) = match op {
+            TokenType::Plus => ("__add__", "__radd__"),
+            TokenType::Minus => ("__sub__", "__rsub__"),
+            TokenType::Star => ("__mul__", "__rmul__"),
+            TokenType::Slash => ("__div__", "__rdiv__"),
+            TokenType::Less => ("__lt__", "__gt__"),
+            TokenType::LessEqual => ("__le__", "__ge__"),
+            TokenType::Greater => ("__gt__", "__lt__"),
+            TokenType::GreaterEqual => ("__ge__", "__le__"),
+            TokenType::Equals | TokenType::BangEqual => ("__eq__", "__eq__"),
+            _ => ("", ""),
+        };
+
+        let mut result = None;
+        if let Object::Instance(inst) = &left {
+            result = self.call_operator_method(inst, method, vec![right.clone()], operator);
+        }
+        if result.is_none() {
+            if let Object::Instance(inst) = &right {
+                result = self.call_operator_method(inst, reflected, vec![left.clone()], operator);
+            }
+        }
+
+        match (result, op) {
+            (Some(value), TokenType::BangEqual) => Ok(Object::Bool(!self.is_truthy(&value?))),
+            (Some(value), _) => value,
+            (None, TokenType::Equals) => Ok(Object::Bool(Self::is_identical(&left, &right))),
+            (None, TokenType::BangEqual) => Ok(Object::Bool(!Self::is_identical(&left, &right))),
+            (None, _) => Err(LoxResult::runtime_error(
+                operator,
+                &format!(
+                    "Unsupported operand types for '{}': '{}' and '{}'.",
+                    operator.as_string(),
+                    left.type_name(),
+                    right.type_name()
+                ),
+            )),
+        }
+    }
+
+    fn method_key(name: &Token, owner: &str) -> String {
+        if name.is_private() {
+            private_key(&name.as_string(), owner)
+        } else {
+            name.as_string()
+        }
+    }
+
+    fn is_identical(left: &Object, right: &Object) -> bool {
+        match (left, right) {
+            (Object::Instance(a), Object::Instance(b)) => Rc::ptr_eq(a, b) || a.same_data(b),
+            _ => false,
+        }
+    }
+
+    // Returns None when the instance's class does not define the method
+    fn call_operator_method(
+        &self,
+        instance: &Rc<LoxInstance>,
+        name: &str,
+        arguments: Vec<Object>,
+        token: &Token,
+    ) -> Option<Result<Object, LoxResult>> {
+        if let Some(Object::Func(method)) = instance.get_method(name, instance) {
+            if !method.accepts(arguments.len()) {
+                return Some(Err(LoxResult::runtime_error(
+                    token,
+                    &format!(
+                        "Operator method '{name}' must take {} but takes {}.",
+                        match arguments.len() {
+                            1 => "1 parameter".to_string(),
+                            n => format!("{n} parameters"),
+                        },
+                        method.arity_text(),
+                    ),
+                )));
+            }
+            Some(method.call(self, arguments, None))
+        } else {
+            None
+        }
+    }
+
+    fn unsupported_index(&self, object: &Object, method: &str, bracket: &Token) -> LoxResult {
+        if let Object::Instance(inst) = object {
+            LoxResult::runtime_error(
+                bracket,
+                &format!(
+                    "Unsupported operand for indexing: '{}' has no '{}' method.",
+                    inst.class_name(),
+                    method
+                ),
+            )
+        } else {
+            LoxResult::runtime_error(
+                bracket,
+                &format!("Can't index a value of type '{}'.", object.type_name()),
+            )
+        }
+    }
+
     fn look_up_variable(&self, name: &Token, expr: Rc<Expr>) -> Result<Object, LoxResult> {
         if let Some(distance) = self.locals.borrow().get(&expr) {
             self.environment
@@ -463,3 +1190,15 @@ impl Interpreter {
         }
     }
 }
+
+// Wraps the result of date and time arithmetic, which fails when it leaves
+// the supported range
+fn time_value<T>(
+    operator: &Token,
+    result: Result<T, String>,
+    wrap: fn(Rc<T>) -> Object,
+) -> Result<Object, LoxResult> {
+    result
+        .map(|value| wrap(Rc::new(value)))
+        .map_err(|e| LoxResult::runtime_error(operator, &e))
+}
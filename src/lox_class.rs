@@ -11,19 +11,25 @@ use std::collections::HashMap;
 #[derive(Debug, Clone, PartialEq)]
 pub struct LoxClass {
     name: String,
+    fields: Option<Vec<String>>,
     methods: HashMap<String, Object>,
+    abstract_methods: Vec<String>,
     superclass: Option<Rc<LoxClass>>,
 }
 
 impl LoxClass {
     pub fn new(
         name: &str,
+        fields: Option<Vec<String>>,
         superclass: Option<Rc<LoxClass>>,
         methods: HashMap<String, Object>,
+        abstract_methods: Vec<String>,
     ) -> Self {
         Self {
             name: name.to_string(),
+            fields,
             methods,
+            abstract_methods,
             superclass,
         }
     }
@@ -31,18 +37,71 @@ impl LoxClass {
     pub fn instantiate(
         &self,
         interpreter: &Interpreter,
-        arguments: Vec<Object>,
+        arguments: CallArguments,
         klass: Rc<LoxClass>,
     ) -> Result<Object, LoxResult> {
-        let instance = Object::Instance(Rc::new(LoxInstance::new(klass)));
+        let missing = self.unimplemented_methods();
+        if !missing.is_empty() {
+            return Err(LoxResult::native_error(&format!(
+                "Can't instantiate abstract class '{}' with unimplemented methods: {}.",
+                self.name,
+                missing.join(", ")
+            )));
+        }
+
+        let instance = Rc::new(LoxInstance::new(klass));
+        if let Some(fields) = &self.fields {
+            let arguments = bind_arguments(&self.name, fields, 0, false, arguments)?;
+            for (field, value) in fields.iter().zip(arguments.values) {
+                instance.set(field, value.unwrap_or(Object::Nil));
+            }
+            return Ok(Object::Instance(instance));
+        }
+
+        let instance = Object::Instance(instance);
         if let Some(Object::Func(initializer)) = self.find_method("init") {
             if let Object::Func(init) = initializer.bind(&instance) {
-                init.call(interpreter, arguments, None)?;
+                init.call_with(interpreter, arguments, None)?;
             }
+        } else {
+            bind_arguments(&self.name, &[], 0, false, arguments)?;
         }
         Ok(instance)
     }
 
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    // Only data classes have fields that are known up front
+    pub fn fields(&self) -> Option<&[String]> {
+        self.fields.as_deref()
+    }
+
+    // Abstract methods declared anywhere up the hierarchy that nothing implements
+    pub fn unimplemented_methods(&self) -> Vec<String> {
+        let mut missing = Vec::new();
+        let mut klass = Some(self);
+        while let Some(k) = klass {
+            for name in &k.abstract_methods {
+                if self.find_method(name).is_none() && !missing.contains(name) {
+                    missing.push(name.clone());
+                }
+            }
+            klass = k.superclass.as_deref();
+        }
+        missing.sort();
+        missing
+    }
+
+    pub fn inherits_from(&self, other: &Rc<LoxClass>) -> bool {
+        std::ptr::eq(self, other.as_ref())
+            || self
+                .superclass
+                .as_ref()
+                .is_some_and(|superclass| superclass.inherits_from(other))
+    }
+
     pub fn find_method(&self, name: &str) -> Option<Object> {
         if let Some(method) = self.methods.get(name) {
             Some(method.clone())
@@ -59,6 +118,7 @@ impl fmt::Display for LoxClass {
         let methods = self
             .methods
             .keys()
+            .filter(|name| !name.starts_with('#'))
             .cloned()
             .collect::<Vec<String>>()
             .join(", ");
@@ -72,12 +132,23 @@ impl LoxCallable for LoxClass {
         interpreter: &Interpreter,
         arguments: Vec<Object>,
         klass: Option<Rc<LoxClass>>,
+    ) -> Result<Object, LoxResult> {
+        self.call_with(interpreter, CallArguments::positional(arguments), klass)
+    }
+
+    fn call_with(
+        &self,
+        interpreter: &Interpreter,
+        arguments: CallArguments,
+        klass: Option<Rc<LoxClass>>,
     ) -> Result<Object, LoxResult> {
         self.instantiate(interpreter, arguments, klass.unwrap())
     }
 
     fn arity(&self) -> usize {
-        if let Some(Object::Func(initializer)) = self.find_method("init") {
+        if let Some(fields) = &self.fields {
+            fields.len()
+        } else if let Some(Object::Func(initializer)) = self.find_method("init") {
             initializer.arity()
         } else {
             0
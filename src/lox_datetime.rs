@@ -0,0 +1,463 @@
+use std::cmp::Ordering;
+use std::fmt;
+
+const MILLIS_PER_SECOND: i64 = 1000;
+const MILLIS_PER_MINUTE: i64 = 60 * MILLIS_PER_SECOND;
+const MILLIS_PER_HOUR: i64 = 60 * MILLIS_PER_MINUTE;
+const MILLIS_PER_DAY: i64 = 24 * MILLIS_PER_HOUR;
+
+// Offsets are limited to what ISO-8601 allows
+pub const MAX_OFFSET_MINUTES: i32 = 18 * 60;
+
+// Like JavaScript's Date, instants and durations are limited to 100,000,000
+// days either side of the epoch. Every one of them is then exact as a Lox
+// number, and no calculation on them can overflow.
+const MAX_MILLIS: i64 = 100_000_000 * MILLIS_PER_DAY;
+const MIN_YEAR: i64 = -271_821;
+const MAX_YEAR: i64 = 275_760;
+
+pub const WEEKDAYS: [&str; 7] = [
+    "Monday",
+    "Tuesday",
+    "Wednesday",
+    "Thursday",
+    "Friday",
+    "Saturday",
+    "Sunday",
+];
+
+pub const MONTHS: [&str; 12] = [
+    "January",
+    "February",
+    "March",
+    "April",
+    "May",
+    "June",
+    "July",
+    "August",
+    "September",
+    "October",
+    "November",
+    "December",
+];
+
+// An instant in time, stored as milliseconds since the Unix epoch in UTC,
+// along with the fixed UTC offset it is shown in. There is no timezone
+// database, so offsets never change with daylight saving.
+#[derive(Debug, Clone, Copy)]
+pub struct LoxDateTime {
+    millis: i64,
+    offset: i32,
+}
+
+// The calendar fields of a date and time, as seen at some offset
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Civil {
+    pub year: i64,
+    pub month: u32,
+    pub day: u32,
+    pub hour: u32,
+    pub minute: u32,
+    pub second: u32,
+    pub millisecond: u32,
+}
+
+impl Civil {
+    pub fn date(year: i64, month: u32, day: u32) -> Self {
+        Self {
+            year,
+            month,
+            day,
+            hour: 0,
+            minute: 0,
+            second: 0,
+            millisecond: 0,
+        }
+    }
+
+    // Explains the first field that is out of range, if any
+    pub fn validate(&self) -> Result<(), String> {
+        if !(MIN_YEAR..=MAX_YEAR).contains(&self.year) {
+            return Err(format!(
+                "Year {} is out of range {MIN_YEAR} to {MAX_YEAR}.",
+                self.year
+            ));
+        }
+        if !(1..=12).contains(&self.month) {
+            return Err(format!("Month {} is out of range 1 to 12.", self.month));
+        }
+        let days = days_in_month(self.year, self.month);
+        if !(1..=days).contains(&self.day) {
+            return Err(format!(
+                "Day {} is out of range 1 to {days} for {:04}-{:02}.",
+                self.day, self.year, self.month
+            ));
+        }
+        let limits = [
+            ("Hour", self.hour, 23),
+            ("Minute", self.minute, 59),
+            ("Second", self.second, 59),
+            ("Millisecond", self.millisecond, 999),
+        ];
+        for (name, value, max) in limits {
+            if value > max {
+                return Err(format!("{name} {value} is out of range 0 to {max}."));
+            }
+        }
+        Ok(())
+    }
+}
+
+impl LoxDateTime {
+    pub fn from_timestamp(millis: i64, offset: i32) -> Result<Self, String> {
+        check_offset(offset)?;
+        if !(-MAX_MILLIS..=MAX_MILLIS).contains(&millis) {
+            return Err(format!(
+                "Timestamp {millis} is out of range; it must be within {MAX_MILLIS} milliseconds of the epoch."
+            ));
+        }
+        Ok(Self { millis, offset })
+    }
+
+    // The instant whose calendar fields at `offset` minutes are `civil`
+    pub fn from_civil(civil: &Civil, offset: i32) -> Result<Self, String> {
+        civil.validate()?;
+        check_offset(offset)?;
+        let days = days_from_civil(civil.year, civil.month, civil.day);
+        let local = days * MILLIS_PER_DAY
+            + civil.hour as i64 * MILLIS_PER_HOUR
+            + civil.minute as i64 * MILLIS_PER_MINUTE
+            + civil.second as i64 * MILLIS_PER_SECOND
+            + civil.millisecond as i64;
+        Self::from_timestamp(local - offset as i64 * MILLIS_PER_MINUTE, offset)
+    }
+
+    pub fn timestamp(&self) -> i64 {
+        self.millis
+    }
+
+    pub fn offset(&self) -> i32 {
+        self.offset
+    }
+
+    // The same instant shown at another offset
+    pub fn with_offset(&self, offset: i32) -> Result<Self, String> {
+        Self::from_timestamp(self.millis, offset)
+    }
+
+    fn local_millis(&self) -> i64 {
+        self.millis + self.offset as i64 * MILLIS_PER_MINUTE
+    }
+
+    pub fn civil(&self) -> Civil {
+        let local = self.local_millis();
+        let (year, month, day) = civil_from_days(local.div_euclid(MILLIS_PER_DAY));
+        let time = local.rem_euclid(MILLIS_PER_DAY);
+        Civil {
+            year,
+            month,
+            day,
+            hour: (time / MILLIS_PER_HOUR) as u32,
+            minute: (time % MILLIS_PER_HOUR / MILLIS_PER_MINUTE) as u32,
+            second: (time % MILLIS_PER_MINUTE / MILLIS_PER_SECOND) as u32,
+            millisecond: (time % MILLIS_PER_SECOND) as u32,
+        }
+    }
+
+    // 1 for Monday through 7 for Sunday, as in ISO-8601
+    pub fn weekday(&self) -> u32 {
+        // The epoch was a Thursday
+        (self.local_millis().div_euclid(MILLIS_PER_DAY) + 3).rem_euclid(7) as u32 + 1
+    }
+
+    pub fn day_of_year(&self) -> u32 {
+        let civil = self.civil();
+        (days_from_civil(civil.year, civil.month, civil.day) - days_from_civil(civil.year, 1, 1))
+            as u32
+            + 1
+    }
+
+    pub fn plus(&self, duration: &LoxDuration) -> Result<Self, String> {
+        Self::from_timestamp(self.millis + duration.millis, self.offset)
+    }
+
+    pub fn since(&self, other: &LoxDateTime) -> Result<LoxDuration, String> {
+        LoxDuration::new(self.millis - other.millis)
+    }
+}
+
+// Date-times are the same when they are the same instant, whatever offset
+// they are shown at
+impl PartialEq for LoxDateTime {
+    fn eq(&self, other: &Self) -> bool {
+        self.millis == other.millis
+    }
+}
+
+impl PartialOrd for LoxDateTime {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        self.millis.partial_cmp(&other.millis)
+    }
+}
+
+// ISO-8601, like `2024-03-05T09:30:00Z` or `2024-03-05T11:30:00.250+02:00`
+impl fmt::Display for LoxDateTime {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let c = self.civil();
+        write!(
+            f,
+            "{}-{:02}-{:02}T{:02}:{:02}:{:02}",
+            format_year(c.year),
+            c.month,
+            c.day,
+            c.hour,
+            c.minute,
+            c.second
+        )?;
+        if c.millisecond != 0 {
+            write!(f, ".{:03}", c.millisecond)?;
+        }
+        if self.offset == 0 {
+            write!(f, "Z")
+        } else {
+            write!(f, "{}", format_offset(self.offset, true))
+        }
+    }
+}
+
+// A length of time, to the millisecond. Negative durations point backwards.
+#[derive(Debug, Clone, Copy, PartialEq, PartialOrd)]
+pub struct LoxDuration {
+    millis: i64,
+}
+
+impl LoxDuration {
+    pub fn new(millis: i64) -> Result<Self, String> {
+        if !(-MAX_MILLIS..=MAX_MILLIS).contains(&millis) {
+            return Err(format!(
+                "Duration of {millis} milliseconds is out of range; it must be within {MAX_MILLIS}."
+            ));
+        }
+        Ok(Self { millis })
+    }
+
+    pub fn from_unit(amount: f64, unit: &str) -> Result<Self, String> {
+        let one = unit_millis(unit).ok_or_else(|| format!("Unknown duration unit '{unit}'."))?;
+        Self::from_float(amount * one as f64)
+            .map_err(|_| format!("Duration of {amount} {unit} is out of range."))
+    }
+
+    pub fn in_unit(&self, unit: &str) -> Option<f64> {
+        unit_millis(unit).map(|one| self.millis as f64 / one as f64)
+    }
+
+    pub fn plus(&self, other: &LoxDuration) -> Result<Self, String> {
+        Self::new(self.millis + other.millis)
+    }
+
+    pub fn times(&self, factor: f64) -> Result<Self, String> {
+        Self::from_float(self.millis as f64 * factor)
+    }
+
+    pub fn abs(&self) -> Self {
+        Self {
+            millis: self.millis.abs(),
+        }
+    }
+
+    // Checks the range before converting, since `as` saturates
+    fn from_float(millis: f64) -> Result<Self, String> {
+        let millis = millis.round();
+        if millis.is_finite() && millis.abs() <= MAX_MILLIS as f64 {
+            Self::new(millis as i64)
+        } else {
+            Err(format!(
+                "Duration of {millis} milliseconds is out of range; it must be within {MAX_MILLIS}."
+            ))
+        }
+    }
+}
+
+// ISO-8601, like `P1DT2H30M` or `PT0.5S`
+impl fmt::Display for LoxDuration {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.millis < 0 {
+            write!(f, "-")?;
+        }
+        let millis = self.millis.unsigned_abs() as i64;
+        let days = millis / MILLIS_PER_DAY;
+        let hours = millis % MILLIS_PER_DAY / MILLIS_PER_HOUR;
+        let minutes = millis % MILLIS_PER_HOUR / MILLIS_PER_MINUTE;
+        let seconds = (millis % MILLIS_PER_MINUTE) as f64 / MILLIS_PER_SECOND as f64;
+
+        write!(f, "P")?;
+        if days != 0 {
+            write!(f, "{days}D")?;
+        }
+        if hours != 0 || minutes != 0 || seconds != 0.0 || days == 0 {
+            write!(f, "T")?;
+        }
+        if hours != 0 {
+            write!(f, "{hours}H")?;
+        }
+        if minutes != 0 {
+            write!(f, "{minutes}M")?;
+        }
+        if seconds != 0.0 || millis == 0 {
+            write!(f, "{seconds}S")?;
+        }
+        Ok(())
+    }
+}
+
+fn unit_millis(unit: &str) -> Option<i64> {
+    match unit {
+        "days" => Some(MILLIS_PER_DAY),
+        "hours" => Some(MILLIS_PER_HOUR),
+        "minutes" => Some(MILLIS_PER_MINUTE),
+        "seconds" => Some(MILLIS_PER_SECOND),
+        "millis" => Some(1),
+        _ => None,
+    }
+}
+
+pub fn check_offset(offset: i32) -> Result<(), String> {
+    if !(-MAX_OFFSET_MINUTES..=MAX_OFFSET_MINUTES).contains(&offset) {
+        Err(format!(
+            "UTC offset of {offset} minutes is out of range; it must be within 18 hours."
+        ))
+    } else {
+        Ok(())
+    }
+}
+
+pub fn format_offset(offset: i32, colon: bool) -> String {
+    let sign = if offset < 0 { '-' } else { '+' };
+    let separator = if colon { ":" } else { "" };
+    let offset = offset.abs();
+    format!("{sign}{:02}{separator}{:02}", offset / 60, offset % 60)
+}
+
+pub fn format_year(year: i64) -> String {
+    if (0..=9999).contains(&year) {
+        format!("{year:04}")
+    } else {
+        format!("{year:+05}")
+    }
+}
+
+pub fn is_leap_year(year: i64) -> bool {
+    year % 4 == 0 && (year % 100 != 0 || year % 400 == 0)
+}
+
+pub fn days_in_month(year: i64, month: u32) -> u32 {
+    match month {
+        2 if is_leap_year(year) => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
+
+// Days since 1970-01-01 in the proleptic Gregorian calendar, after Howard
+// Hinnant's `days_from_civil`
+fn days_from_civil(year: i64, month: u32, day: u32) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year.rem_euclid(400);
+    let month = month as i64;
+    let day_of_year =
+        (153 * (if month > 2 { month - 3 } else { month + 9 }) + 2) / 5 + day as i64 - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146097 + day_of_era - 719468
+}
+
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let days = days + 719468;
+    let era = days.div_euclid(146097);
+    let day_of_era = days.rem_euclid(146097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let shifted_month = (5 * day_of_year + 2) / 153;
+    let day = (day_of_year - (153 * shifted_month + 2) / 5 + 1) as u32;
+    let month = if shifted_month < 10 {
+        shifted_month + 3
+    } else {
+        shifted_month - 9
+    } as u32;
+    let year = year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+    (year, month, day)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn days_from_civil_matches_known_dates() {
+        assert_eq!(days_from_civil(1970, 1, 1), 0);
+        assert_eq!(days_from_civil(1969, 12, 31), -1);
+        assert_eq!(days_from_civil(2000, 3, 1), 11017);
+        assert_eq!(days_from_civil(0, 1, 1), -719528);
+        assert_eq!(days_from_civil(-1, 12, 31), -719529);
+    }
+
+    #[test]
+    fn civil_days_round_trip_across_eras() {
+        let first = days_from_civil(-1201, 1, 1);
+        let last = days_from_civil(2401, 12, 31);
+        let mut expected = (-1201, 1, 1);
+        for days in first..=last {
+            let civil = civil_from_days(days);
+            assert_eq!(civil, expected, "day {days}");
+            assert_eq!(days_from_civil(civil.0, civil.1, civil.2), days);
+
+            let (year, month, day) = expected;
+            expected = if day < days_in_month(year, month) {
+                (year, month, day + 1)
+            } else if month < 12 {
+                (year, month + 1, 1)
+            } else {
+                (year + 1, 1, 1)
+            };
+        }
+    }
+
+    #[test]
+    fn leap_days_follow_the_gregorian_rules() {
+        for year in [2024, 2000, 0, -4, -400] {
+            assert!(is_leap_year(year), "{year}");
+            let leap_day = days_from_civil(year, 2, 29);
+            assert_eq!(civil_from_days(leap_day), (year, 2, 29));
+            assert_eq!(civil_from_days(leap_day + 1), (year, 3, 1));
+        }
+        for year in [2023, 1900, -1, -100] {
+            assert!(!is_leap_year(year), "{year}");
+            assert!(Civil::date(year, 2, 29).validate().is_err());
+            let end = days_from_civil(year, 2, 28);
+            assert_eq!(civil_from_days(end + 1), (year, 3, 1));
+        }
+    }
+
+    #[test]
+    fn timestamps_are_bounded() {
+        let first = LoxDateTime::from_timestamp(-MAX_MILLIS, 0).unwrap();
+        let last = LoxDateTime::from_timestamp(MAX_MILLIS, 0).unwrap();
+        assert_eq!(first.to_string(), "-271821-04-20T00:00:00Z");
+        assert_eq!(last.to_string(), "+275760-09-13T00:00:00Z");
+        assert!(LoxDateTime::from_timestamp(MAX_MILLIS + 1, 0).is_err());
+        assert!(LoxDateTime::from_timestamp(i64::MIN, 0).is_err());
+        assert!(last.plus(&LoxDuration::new(1).unwrap()).is_err());
+        assert!(LoxDuration::new(i64::MIN).is_err());
+        assert!(check_offset(i32::MIN).is_err());
+    }
+
+    #[test]
+    fn negative_years_display_with_a_sign() {
+        let civil = Civil::date(-1, 12, 31);
+        let datetime = LoxDateTime::from_civil(&civil, 90).unwrap();
+        assert_eq!(datetime.civil(), civil);
+        assert_eq!(datetime.to_string(), "-0001-12-31T00:00:00+01:30");
+    }
+}
@@ -0,0 +1,156 @@
+use crate::native_functions::*;
+use crate::object::*;
+use std::fmt;
+use std::rc::Rc;
+
+// Members of every enum, which no variant can be named after
+pub const ENUM_METHODS: [&str; 3] = ["values", "fromName", "fromOrdinal"];
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct EnumVariant {
+    pub name: String,
+    pub fields: Option<Vec<String>>,
+}
+
+#[derive(Debug)]
+pub struct LoxEnum {
+    name: String,
+    variants: Vec<EnumVariant>,
+}
+
+impl LoxEnum {
+    pub fn new(name: &str, variants: Vec<EnumVariant>) -> Self {
+        Self {
+            name: name.to_string(),
+            variants,
+        }
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn has_variant(&self, name: &str) -> bool {
+        self.variants.iter().any(|v| v.name == name)
+    }
+
+    // A variant without fields is a value; one with fields is a constructor
+    // that builds a value from its payload
+    pub fn variant(self: &Rc<Self>, name: &str) -> Option<Object> {
+        let ordinal = self.variants.iter().position(|v| v.name == name)?;
+        self.value(ordinal).or_else(|| {
+            let owner = Rc::clone(self);
+            let arity = self.variants[ordinal].fields.as_ref().map_or(0, Vec::len);
+            Some(Object::Native(Rc::new(NativeFunction::new(
+                &format!("{}.{name}", self.name),
+                arity,
+                move |_, payload| {
+                    Ok(Object::EnumValue(Rc::new(LoxEnumValue {
+                        owner: Rc::clone(&owner),
+                        ordinal,
+                        payload,
+                    })))
+                },
+            ))))
+        })
+    }
+
+    // Only variants without fields have a value of their own
+    pub fn value(self: &Rc<Self>, ordinal: usize) -> Option<Object> {
+        match self.variants.get(ordinal) {
+            Some(EnumVariant { fields: None, .. }) => {
+                Some(Object::EnumValue(Rc::new(LoxEnumValue {
+                    owner: Rc::clone(self),
+                    ordinal,
+                    payload: Vec::new(),
+                })))
+            }
+            _ => None,
+        }
+    }
+
+    // Every variant in declaration order: the value itself for a variant
+    // without fields, and its constructor for one with fields
+    pub fn values(self: &Rc<Self>) -> Vec<Object> {
+        self.variants
+            .iter()
+            .filter_map(|v| self.variant(&v.name))
+            .collect()
+    }
+}
+
+// Two enums are only the same if they come from the same declaration
+impl PartialEq for LoxEnum {
+    fn eq(&self, other: &Self) -> bool {
+        std::ptr::eq(self, other)
+    }
+}
+
+impl fmt::Display for LoxEnum {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let variants = self
+            .variants
+            .iter()
+            .map(|v| v.name.clone())
+            .collect::<Vec<String>>()
+            .join(", ");
+        write!(f, "<Enum {} {{ {variants} }}>", self.name)
+    }
+}
+
+#[derive(Debug)]
+pub struct LoxEnumValue {
+    owner: Rc<LoxEnum>,
+    ordinal: usize,
+    payload: Vec<Object>,
+}
+
+impl LoxEnumValue {
+    pub fn owner(&self) -> &Rc<LoxEnum> {
+        &self.owner
+    }
+
+    pub fn name(&self) -> &str {
+        &self.owner.variants[self.ordinal].name
+    }
+
+    pub fn ordinal(&self) -> usize {
+        self.ordinal
+    }
+
+    pub fn payload(&self) -> &[Object] {
+        &self.payload
+    }
+
+    pub fn field(&self, name: &str) -> Option<Object> {
+        let fields = self.owner.variants[self.ordinal].fields.as_ref()?;
+        let position = fields.iter().position(|f| f == name)?;
+        self.payload.get(position).cloned()
+    }
+
+    pub fn same_enum(&self, other: &LoxEnumValue) -> bool {
+        Rc::ptr_eq(&self.owner, &other.owner)
+    }
+}
+
+impl PartialEq for LoxEnumValue {
+    fn eq(&self, other: &Self) -> bool {
+        self.same_enum(other) && self.ordinal == other.ordinal && self.payload == other.payload
+    }
+}
+
+impl fmt::Display for LoxEnumValue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}.{}", self.owner.name, self.name())?;
+        if self.owner.variants[self.ordinal].fields.is_some() {
+            let payload = self
+                .payload
+                .iter()
+                .map(|v| v.repr())
+                .collect::<Vec<String>>()
+                .join(", ");
+            write!(f, "({payload})")?;
+        }
+        Ok(())
+    }
+}
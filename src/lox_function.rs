@@ -1,8 +1,11 @@
 use crate::callable::*;
 use crate::environment::*;
 use crate::error::*;
+use crate::expr::*;
 use crate::interpreter::*;
 use crate::lox_class::*;
+use crate::lox_generator::*;
+use crate::lox_list::*;
 use crate::object::*;
 use crate::stmt::*;
 use crate::token::*;
@@ -13,7 +16,10 @@ use std::rc::Rc;
 pub struct LoxFunction {
     name: Token,
     is_initializer: bool,
+    is_generator: bool,
     params: Rc<Vec<Token>>,
+    defaults: Rc<Vec<Option<Rc<Expr>>>>,
+    rest: Option<Token>,
     body: Rc<Vec<Rc<Stmt>>>,
     closure: Rc<RefCell<Environment>>,
 }
@@ -29,7 +35,10 @@ impl Clone for LoxFunction {
         Self {
             name: self.name.dup(),
             is_initializer: self.is_initializer,
+            is_generator: self.is_generator,
             params: Rc::clone(&self.params),
+            defaults: Rc::clone(&self.defaults),
+            rest: self.rest.as_ref().map(Token::dup),
             body: Rc::clone(&self.body),
             closure: Rc::clone(&self.closure),
         }
@@ -50,6 +59,7 @@ impl fmt::Display for LoxFunction {
             .params
             .iter()
             .map(|p| p.as_string())
+            .chain(self.rest.iter().map(|r| format!("...{}", r.as_string())))
             .collect::<Vec<String>>()
             .join(", ");
         write!(f, "<Function {}({})>", self.name.as_string(), paramlist)
@@ -65,7 +75,10 @@ impl LoxFunction {
         Self {
             name: declaration.name.dup(),
             is_initializer,
+            is_generator: declaration.is_generator,
             params: Rc::clone(&declaration.params),
+            defaults: Rc::clone(&declaration.defaults),
+            rest: declaration.rest.as_ref().map(Token::dup),
             body: Rc::clone(&declaration.body),
             closure: Rc::clone(closure),
         }
@@ -75,13 +88,28 @@ impl LoxFunction {
         let environment = RefCell::new(Environment::new_with_enclosing(Rc::clone(&self.closure)));
         environment.borrow_mut().define("this", instance.clone());
         Object::Func(Rc::new(Self {
-            name: self.name.dup(),
-            is_initializer: self.is_initializer,
-            params: Rc::clone(&self.params),
-            body: Rc::clone(&self.body),
             closure: Rc::new(environment),
+            ..self.clone()
         }))
     }
+
+    // Whether a call with `count` positional arguments binds every parameter
+    pub fn accepts(&self, count: usize) -> bool {
+        let required = self.defaults.iter().filter(|d| d.is_none()).count();
+        count >= required && (count <= self.params.len() || self.rest.is_some())
+    }
+
+    // How many parameters the function takes, like "2", "1 to 3" or "at least 1"
+    pub fn arity_text(&self) -> String {
+        let required = self.defaults.iter().filter(|d| d.is_none()).count();
+        if self.rest.is_some() {
+            format!("at least {required}")
+        } else if required == self.params.len() {
+            required.to_string()
+        } else {
+            format!("{required} to {}", self.params.len())
+        }
+    }
 }
 
 impl LoxCallable for LoxFunction {
@@ -89,12 +117,61 @@ impl LoxCallable for LoxFunction {
         &self,
         interpreter: &Interpreter,
         arguments: Vec<Object>,
+        klass: Option<Rc<LoxClass>>,
+    ) -> Result<Object, LoxResult> {
+        self.call_with(interpreter, CallArguments::positional(arguments), klass)
+    }
+
+    fn call_with(
+        &self,
+        interpreter: &Interpreter,
+        arguments: CallArguments,
         _klass: Option<Rc<LoxClass>>,
     ) -> Result<Object, LoxResult> {
-        let mut e = Environment::new_with_enclosing(Rc::clone(&self.closure));
+        let params = self
+            .params
+            .iter()
+            .map(|p| p.as_string())
+            .collect::<Vec<String>>();
+        let optional = self.defaults.iter().filter(|d| d.is_some()).count();
+        let arguments = bind_arguments(
+            &self.name.as_string(),
+            &params,
+            optional,
+            self.rest.is_some(),
+            arguments,
+        )?;
+
+        // Defaults are evaluated in the new environment, after the
+        // parameters before them are defined
+        let e = Rc::new(RefCell::new(Environment::new_with_enclosing(Rc::clone(
+            &self.closure,
+        ))));
+        let bindings = params
+            .iter()
+            .zip(self.defaults.iter())
+            .zip(arguments.values);
+        for ((param, default), value) in bindings {
+            let value = match (value, default) {
+                (Some(value), _) => value,
+                (None, Some(default)) => interpreter.evaluate_in(default.clone(), Rc::clone(&e))?,
+                (None, None) => Object::Nil,
+            };
+            e.borrow_mut().define(param, value);
+        }
+        if let Some(rest) = &self.rest {
+            e.borrow_mut().define(
+                &rest.as_string(),
+                Object::List(Rc::new(LoxList::new(arguments.rest))),
+            );
+        }
 
-        for (param, arg) in self.params.iter().zip(arguments.iter()) {
-            e.define(&param.as_string(), arg.clone());
+        if self.is_generator {
+            return Ok(Object::Generator(Rc::new(LoxGenerator::new(
+                &self.name.as_string(),
+                Rc::clone(&self.body),
+                e,
+            ))));
         }
 
         match interpreter.execute_block(&self.body, e) {
@@ -105,7 +182,7 @@ impl LoxCallable for LoxFunction {
                     Ok(value)
                 }
             }
-            Err(e) => return Err(e),
+            Err(e) => Err(e),
             Ok(_) => {
                 if self.is_initializer {
                     self.closure.borrow().get_at(0, "this")
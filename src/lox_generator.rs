@@ -0,0 +1,300 @@
+use crate::environment::*;
+use crate::error::*;
+use crate::interpreter::*;
+use crate::lox_iterator::*;
+use crate::object::*;
+use crate::stmt::*;
+use std::cell::{Cell, RefCell};
+use std::fmt;
+use std::rc::Rc;
+
+// A generator body can't run on the Rust call stack, since it has to stop at
+// every `yield` and pick up from there later. Statements that may yield are
+// instead unfolded into frames on an explicit stack that survives between
+// resumptions. Statements without a `yield` inside run through the
+// interpreter as usual.
+enum Frame {
+    Block {
+        statements: Rc<Vec<Rc<Stmt>>>,
+        position: usize,
+        environment: Rc<RefCell<Environment>>,
+    },
+    While {
+        stmt: Rc<WhileStmt>,
+        environment: Rc<RefCell<Environment>>,
+    },
+    ForIn {
+        stmt: Rc<ForInStmt>,
+        iterator: LoxIterator,
+        environment: Rc<RefCell<Environment>>,
+    },
+}
+
+impl Frame {
+    fn is_loop(&self) -> bool {
+        !matches!(self, Frame::Block { .. })
+    }
+
+    fn close(&self) {
+        if let Frame::ForIn { iterator, .. } = self {
+            iterator.close();
+        }
+    }
+}
+
+enum Action {
+    Run(Rc<Stmt>, Rc<RefCell<Environment>>),
+    Push(Frame),
+    Pop,
+}
+
+pub struct LoxGenerator {
+    name: String,
+    frames: RefCell<Vec<Frame>>,
+    peeked: RefCell<Option<Object>>,
+    running: Cell<bool>,
+}
+
+impl LoxGenerator {
+    pub fn new(name: &str, body: Rc<Vec<Rc<Stmt>>>, environment: Rc<RefCell<Environment>>) -> Self {
+        Self {
+            name: name.to_string(),
+            frames: RefCell::new(vec![Frame::Block {
+                statements: body,
+                position: 0,
+                environment,
+            }]),
+            peeked: RefCell::new(None),
+            running: Cell::new(false),
+        }
+    }
+
+    pub fn has_next(&self, interpreter: &Interpreter) -> Result<bool, LoxResult> {
+        if self.peeked.borrow().is_none() {
+            let value = self.resume(interpreter)?;
+            self.peeked.replace(value);
+        }
+        Ok(self.peeked.borrow().is_some())
+    }
+
+    // Returns None once the generator body has run to completion
+    pub fn next(&self, interpreter: &Interpreter) -> Result<Option<Object>, LoxResult> {
+        if let Some(value) = self.peeked.take() {
+            Ok(Some(value))
+        } else {
+            self.resume(interpreter)
+        }
+    }
+
+    pub fn close(&self) {
+        for frame in self.frames.take().iter() {
+            frame.close();
+        }
+        self.peeked.take();
+    }
+
+    fn resume(&self, interpreter: &Interpreter) -> Result<Option<Object>, LoxResult> {
+        if self.running.replace(true) {
+            return Err(LoxResult::native_error(&format!(
+                "Generator '{}' is already running.",
+                self.name
+            )));
+        }
+
+        // The frames are moved out while running so that Lox code called from
+        // the body never observes them half-updated
+        let mut frames = self.frames.take();
+        let result = Self::run(interpreter, &mut frames);
+        if result.is_err() {
+            frames.iter().for_each(Frame::close);
+            frames.clear();
+        }
+        self.frames.replace(frames);
+        self.running.set(false);
+        result
+    }
+
+    fn run(
+        interpreter: &Interpreter,
+        frames: &mut Vec<Frame>,
+    ) -> Result<Option<Object>, LoxResult> {
+        while let Some(frame) = frames.last_mut() {
+            let action = match frame {
+                Frame::Block {
+                    statements,
+                    position,
+                    environment,
+                } => match statements.get(*position) {
+                    Some(stmt) => {
+                        *position += 1;
+                        Action::Run(Rc::clone(stmt), Rc::clone(environment))
+                    }
+                    None => Action::Pop,
+                },
+                Frame::While { stmt, environment } => {
+                    let condition =
+                        interpreter.evaluate_in(stmt.condition.clone(), Rc::clone(environment))?;
+                    if interpreter.is_truthy(&condition) {
+                        Action::Push(Frame::Block {
+                            statements: Rc::new(vec![stmt.body.clone()]),
+                            position: 0,
+                            environment: Rc::clone(environment),
+                        })
+                    } else {
+                        Action::Pop
+                    }
+                }
+                Frame::ForIn {
+                    stmt,
+                    iterator,
+                    environment,
+                } => match iterator.next(interpreter, &stmt.name)? {
+                    Some(value) => {
+                        let mut e = Environment::new_with_enclosing(Rc::clone(environment));
+                        e.define(&stmt.name.as_string(), value);
+                        Action::Push(Frame::Block {
+                            statements: Rc::new(vec![stmt.body.clone()]),
+                            position: 0,
+                            environment: Rc::new(RefCell::new(e)),
+                        })
+                    }
+                    None => Action::Pop,
+                },
+            };
+
+            match action {
+                Action::Pop => {
+                    frames.pop();
+                }
+                Action::Push(frame) => frames.push(frame),
+                Action::Run(stmt, environment) => {
+                    if contains_yield(&stmt) {
+                        if let Some(value) = Self::unfold(interpreter, &stmt, environment, frames)?
+                        {
+                            return Ok(Some(value));
+                        }
+                        continue;
+                    }
+
+                    match interpreter.execute_in(stmt, environment) {
+                        Ok(()) => {}
+                        Err(LoxResult::Break) => {
+                            while let Some(frame) = frames.pop() {
+                                frame.close();
+                                if frame.is_loop() {
+                                    break;
+                                }
+                            }
+                        }
+                        Err(LoxResult::ReturnValue { .. }) => {
+                            frames.iter().for_each(Frame::close);
+                            frames.clear();
+                        }
+                        Err(e) => return Err(e),
+                    }
+                }
+            }
+        }
+
+        Ok(None)
+    }
+
+    // Turns a statement that contains a `yield` into frames, or produces the
+    // yielded value if the statement is the `yield` itself
+    fn unfold(
+        interpreter: &Interpreter,
+        stmt: &Stmt,
+        environment: Rc<RefCell<Environment>>,
+        frames: &mut Vec<Frame>,
+    ) -> Result<Option<Object>, LoxResult> {
+        match stmt {
+            Stmt::Yield(y) => {
+                let value = if let Some(value) = y.value.clone() {
+                    interpreter.evaluate_in(value, environment)?
+                } else {
+                    Object::Nil
+                };
+                return Ok(Some(value));
+            }
+            Stmt::Block(b) => frames.push(Frame::Block {
+                statements: Rc::clone(&b.statements),
+                position: 0,
+                environment: Rc::new(RefCell::new(Environment::new_with_enclosing(environment))),
+            }),
+            Stmt::If(i) => {
+                let condition =
+                    interpreter.evaluate_in(i.condition.clone(), Rc::clone(&environment))?;
+                let branch = if interpreter.is_truthy(&condition) {
+                    Some(i.then_branch.clone())
+                } else {
+                    i.else_branch.clone()
+                };
+                if let Some(branch) = branch {
+                    frames.push(Frame::Block {
+                        statements: Rc::new(vec![branch]),
+                        position: 0,
+                        environment,
+                    });
+                }
+            }
+            Stmt::While(w) => frames.push(Frame::While {
+                stmt: Rc::clone(w),
+                environment,
+            }),
+            Stmt::ForIn(f) => {
+                let iterable =
+                    interpreter.evaluate_in(f.iterable.clone(), Rc::clone(&environment))?;
+                frames.push(Frame::ForIn {
+                    stmt: Rc::clone(f),
+                    iterator: LoxIterator::new(interpreter, iterable, &f.name)?,
+                    environment,
+                });
+            }
+            Stmt::Match(m) => {
+                if let Some((body, environment)) = interpreter.select_case(m, environment)? {
+                    frames.push(Frame::Block {
+                        statements: Rc::new(vec![body]),
+                        position: 0,
+                        environment,
+                    });
+                }
+            }
+            _ => panic!("statement without yield can't be unfolded"),
+        }
+        Ok(None)
+    }
+}
+
+// Nested functions and classes are not searched: a `yield` in there belongs to them
+fn contains_yield(stmt: &Stmt) -> bool {
+    match stmt {
+        Stmt::Yield(_) => true,
+        Stmt::Block(b) => b.statements.iter().any(|s| contains_yield(s)),
+        Stmt::If(i) => {
+            contains_yield(&i.then_branch)
+                || i.else_branch.as_ref().is_some_and(|s| contains_yield(s))
+        }
+        Stmt::While(w) => contains_yield(&w.body),
+        Stmt::ForIn(f) => contains_yield(&f.body),
+        Stmt::Match(m) => m.bodies.iter().any(|s| contains_yield(s)),
+        _ => false,
+    }
+}
+
+impl fmt::Display for LoxGenerator {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "<Generator {}>", self.name)
+    }
+}
+
+impl fmt::Debug for LoxGenerator {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self)
+    }
+}
+
+impl PartialEq for LoxGenerator {
+    fn eq(&self, other: &Self) -> bool {
+        std::ptr::eq(self, other)
+    }
+}
@@ -1,4 +1,5 @@
 use crate::lox_class::*;
+use crate::native_methods::*;
 use crate::object::*;
 use crate::token::*;
 use crate::LoxResult;
@@ -22,15 +23,24 @@ impl LoxInstance {
         }
     }
 
-    pub fn get(&self, name: &Token, this: &Rc<LoxInstance>) -> Result<Object, LoxResult> {
-        if let Entry::Occupied(o) = self.fields.borrow_mut().entry(name.as_string()) {
+    // `key` is the name the member is stored under, which for private members
+    // includes the declaring class; `name` is what the user wrote
+    pub fn get(
+        &self,
+        key: &str,
+        name: &Token,
+        this: &Rc<LoxInstance>,
+    ) -> Result<Object, LoxResult> {
+        if let Entry::Occupied(o) = self.fields.borrow_mut().entry(key.to_string()) {
             Ok(o.get().clone())
-        } else if let Some(method) = self.klass.find_method(&name.as_string()) {
+        } else if let Some(method) = self.klass.find_method(key) {
             if let Object::Func(func) = method {
-                Ok(func.bind(&Object::Instance(Rc::clone(&this))))
+                Ok(func.bind(&Object::Instance(Rc::clone(this))))
             } else {
                 panic!("tried to bind 'this' to a non-function {method:?}");
             }
+        } else if key == "copy" && self.klass.fields().is_some() {
+            Ok(data_copy_method(this))
         } else {
             Err(LoxResult::runtime_error(
                 name,
@@ -39,22 +49,109 @@ impl LoxInstance {
         }
     }
 
-    pub fn set(&self, name: &Token, value: Object) {
-        self.fields.borrow_mut().insert(name.as_string(), value);
+    pub fn get_method(&self, name: &str, this: &Rc<LoxInstance>) -> Option<Object> {
+        if let Some(Object::Func(func)) = self.klass.find_method(name) {
+            Some(func.bind(&Object::Instance(Rc::clone(this))))
+        } else {
+            None
+        }
+    }
+
+    pub fn class(&self) -> Rc<LoxClass> {
+        Rc::clone(&self.klass)
+    }
+
+    pub fn class_name(&self) -> String {
+        self.klass.name().to_string()
+    }
+
+    // Instances of the same data class are equal when all their fields are
+    pub fn same_data(&self, other: &LoxInstance) -> bool {
+        match self.klass.fields() {
+            Some(fields) if Rc::ptr_eq(&self.klass, &other.klass) => fields
+                .iter()
+                .all(|field| self.fields.borrow().get(field) == other.fields.borrow().get(field)),
+            _ => false,
+        }
+    }
+
+    pub fn field(&self, name: &str) -> Option<Object> {
+        self.fields.borrow().get(name).cloned()
+    }
+
+    // The fields outside code can see: a data class's in declaration order,
+    // or any other instance's sorted by name
+    pub fn public_fields(&self) -> Vec<(String, Object)> {
+        if let Some(names) = self.klass.fields() {
+            return names
+                .iter()
+                .map(|name| (name.clone(), self.field(name).unwrap_or(Object::Nil)))
+                .collect();
+        }
+
+        let mut fields = self
+            .fields
+            .borrow()
+            .iter()
+            .filter(|(name, _)| !name.starts_with('#'))
+            .map(|(name, value)| (name.clone(), value.clone()))
+            .collect::<Vec<(String, Object)>>();
+        fields.sort_by(|a, b| a.0.cmp(&b.0));
+        fields
+    }
+
+    // Empties the instance, returning what its fields were
+    pub fn take_fields(&self) -> Vec<Object> {
+        self.fields.take().into_values().collect()
+    }
+
+    pub fn set(&self, key: &str, value: Object) {
+        self.fields.borrow_mut().insert(key.to_string(), value);
     }
 }
 
+pub fn private_key(name: &str, owner: &str) -> String {
+    format!("{name}@{owner}")
+}
+
 impl fmt::Display for LoxInstance {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        let mut fields = Vec::new();
-        for (k, v) in self.fields.borrow().iter() {
-            fields.push(format!("{k}={v}"));
+        let this = self as *const Self as *const ();
+        if let Some(names) = self.klass.fields() {
+            let placeholder = format!("{}(...)", self.klass.name());
+            return print_once(f, this, &placeholder, |f| {
+                let fields = names
+                    .iter()
+                    .map(|name| {
+                        let value = self.field(name).unwrap_or(Object::Nil);
+                        format!("{name}={}", value.repr())
+                    })
+                    .collect::<Vec<String>>()
+                    .join(", ");
+                write!(f, "{}({fields})", self.klass.name())
+            });
         }
-        write!(
-            f,
-            "<Instance of {} {{ {} }}>",
-            self.klass,
-            fields.join(", ")
-        )
+
+        let placeholder = format!("<Instance of {} {{ ... }}>", self.klass);
+        print_once(f, this, &placeholder, |f| {
+            let mut fields = Vec::new();
+            for (k, v) in self.fields.borrow().iter() {
+                if !k.starts_with('#') {
+                    fields.push(format!("{k}={v}"));
+                }
+            }
+            write!(
+                f,
+                "<Instance of {} {{ {} }}>",
+                self.klass,
+                fields.join(", ")
+            )
+        })
+    }
+}
+
+impl Drop for LoxInstance {
+    fn drop(&mut self) {
+        release(self.take_fields());
     }
 }
@@ -0,0 +1,163 @@
+use crate::callable::*;
+use crate::error::*;
+use crate::interpreter::*;
+use crate::lox_generator::*;
+use crate::lox_instance::*;
+use crate::lox_list::*;
+use crate::lox_range::*;
+use crate::object::*;
+use crate::token::*;
+use std::rc::Rc;
+
+// The state of a for-in loop over any iterable value
+pub enum LoxIterator {
+    List {
+        list: Rc<LoxList>,
+        position: usize,
+    },
+    Values {
+        values: Vec<Object>,
+        position: usize,
+    },
+    Range {
+        range: Rc<LoxRange>,
+        position: usize,
+    },
+    Protocol {
+        iterator: Rc<LoxInstance>,
+    },
+    Generator {
+        generator: Rc<LoxGenerator>,
+    },
+}
+
+impl LoxIterator {
+    pub fn new(
+        interpreter: &Interpreter,
+        iterable: Object,
+        token: &Token,
+    ) -> Result<LoxIterator, LoxResult> {
+        match iterable {
+            Object::List(list) => Ok(LoxIterator::List { list, position: 0 }),
+            Object::Map(map) => Ok(LoxIterator::Values {
+                values: map.keys(),
+                position: 0,
+            }),
+            Object::Str(s) => Ok(LoxIterator::Values {
+                values: s.chars().map(|c| Object::Str(c.to_string())).collect(),
+                position: 0,
+            }),
+            Object::Range(range) => Ok(LoxIterator::Range { range, position: 0 }),
+            Object::Enum(lox_enum) => Ok(LoxIterator::Values {
+                values: lox_enum.values(),
+                position: 0,
+            }),
+            Object::Generator(generator) => Ok(LoxIterator::Generator { generator }),
+            Object::Instance(inst) => {
+                let iterator = call_method(interpreter, &inst, "iterator", token)?;
+                if let Object::Generator(generator) = iterator {
+                    Ok(LoxIterator::Generator { generator })
+                } else if let Object::Instance(iterator) = iterator {
+                    for method in ["hasNext", "next"] {
+                        if iterator.get_method(method, &iterator).is_none() {
+                            return Err(LoxResult::runtime_error(
+                                token,
+                                &format!(
+                                    "Iterator of type '{}' has no '{method}' method.",
+                                    iterator.class_name()
+                                ),
+                            ));
+                        }
+                    }
+                    Ok(LoxIterator::Protocol { iterator })
+                } else {
+                    Err(LoxResult::runtime_error(
+                        token,
+                        &format!(
+                            "'{}.iterator()' must return an instance, not '{}'.",
+                            inst.class_name(),
+                            iterator.type_name()
+                        ),
+                    ))
+                }
+            }
+            _ => Err(LoxResult::runtime_error(
+                token,
+                &format!(
+                    "Can't iterate over a value of type '{}'.",
+                    iterable.type_name()
+                ),
+            )),
+        }
+    }
+
+    pub fn next(
+        &mut self,
+        interpreter: &Interpreter,
+        token: &Token,
+    ) -> Result<Option<Object>, LoxResult> {
+        match self {
+            LoxIterator::List { list, position } => {
+                let item = list.borrow().get(*position).cloned();
+                *position += 1;
+                Ok(item)
+            }
+            LoxIterator::Values { values, position } => {
+                let item = values.get(*position).cloned();
+                *position += 1;
+                Ok(item)
+            }
+            LoxIterator::Range { range, position } => {
+                let item = range.get(*position).map(Object::Num);
+                *position += 1;
+                Ok(item)
+            }
+            LoxIterator::Protocol { iterator } => {
+                match call_method(interpreter, iterator, "hasNext", token)? {
+                    Object::Bool(false) | Object::Nil => Ok(None),
+                    _ => Ok(Some(call_method(interpreter, iterator, "next", token)?)),
+                }
+            }
+            LoxIterator::Generator { generator } => {
+                generator.next(interpreter).map_err(|e| match e {
+                    LoxResult::SystemError { message } => LoxResult::runtime_error(token, &message),
+                    e => e,
+                })
+            }
+        }
+    }
+
+    // Lets a generator release its suspended state when a loop exits early
+    pub fn close(&self) {
+        if let LoxIterator::Generator { generator } = self {
+            generator.close();
+        }
+    }
+}
+
+fn call_method(
+    interpreter: &Interpreter,
+    instance: &Rc<LoxInstance>,
+    name: &str,
+    token: &Token,
+) -> Result<Object, LoxResult> {
+    match instance.get_method(name, instance) {
+        Some(Object::Func(method)) if method.accepts(0) => {
+            method.call(interpreter, Vec::new(), None)
+        }
+        Some(_) => Err(LoxResult::runtime_error(
+            token,
+            &format!(
+                "'{}.{name}()' must not take any parameters.",
+                instance.class_name()
+            ),
+        )),
+        None => Err(LoxResult::runtime_error(
+            token,
+            &format!(
+                "Can't iterate over an instance of '{}' without an '{name}' method.",
+                instance.class_name()
+            ),
+        )),
+    }
+}
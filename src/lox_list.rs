@@ -0,0 +1,63 @@
+use crate::object::*;
+use std::cell::RefCell;
+use std::ops::Deref;
+
+// The elements of a list, borrowed through the RefCell like any other
+// shared mutable value
+#[derive(Debug, Default, PartialEq)]
+pub struct LoxList {
+    elements: RefCell<Vec<Object>>,
+}
+
+impl LoxList {
+    pub fn new(elements: Vec<Object>) -> Self {
+        Self {
+            elements: RefCell::new(elements),
+        }
+    }
+
+    pub fn take(&self) -> Vec<Object> {
+        self.elements.take()
+    }
+}
+
+impl Deref for LoxList {
+    type Target = RefCell<Vec<Object>>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.elements
+    }
+}
+
+impl Drop for LoxList {
+    fn drop(&mut self) {
+        release(self.take());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::rc::Rc;
+
+    #[test]
+    fn deeply_nested_lists_drop_without_recursing() {
+        let mut list = Object::List(Rc::new(LoxList::default()));
+        for _ in 0..1_000_000 {
+            list = Object::List(Rc::new(LoxList::new(vec![list])));
+        }
+        drop(list);
+    }
+
+    #[test]
+    fn a_list_that_contains_itself_prints_once() {
+        let list = Rc::new(LoxList::new(vec![Object::Num(1.0)]));
+        list.borrow_mut().push(Object::List(Rc::clone(&list)));
+        let outer = Object::List(Rc::new(LoxList::new(vec![
+            Object::List(Rc::clone(&list)),
+            Object::List(Rc::clone(&list)),
+        ])));
+        assert_eq!(outer.to_string(), "[[1, [...]], [1, [...]]]");
+        list.take();
+    }
+}
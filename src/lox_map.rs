@@ -0,0 +1,146 @@
+use crate::object::*;
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::fmt;
+
+// Only values with a stable identity by content can be used as keys
+#[derive(Debug, PartialEq, Eq, Hash)]
+enum MapKey {
+    Num(u64),
+    Str(String),
+    Bool(bool),
+    Nil,
+}
+
+impl MapKey {
+    fn from(key: &Object) -> Option<MapKey> {
+        match key {
+            Object::Num(n) => Some(MapKey::Num(if *n == 0.0 { 0 } else { n.to_bits() })),
+            Object::Str(s) => Some(MapKey::Str(s.clone())),
+            Object::Bool(b) => Some(MapKey::Bool(*b)),
+            Object::Nil => Some(MapKey::Nil),
+            _ => None,
+        }
+    }
+}
+
+// A map that remembers insertion order, so iteration and printing are stable
+#[derive(Debug, Default)]
+pub struct LoxMap {
+    entries: RefCell<Vec<(Object, Object)>>,
+    index: RefCell<HashMap<MapKey, usize>>,
+}
+
+impl LoxMap {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn is_valid_key(key: &Object) -> bool {
+        MapKey::from(key).is_some()
+    }
+
+    pub fn get(&self, key: &Object) -> Option<Object> {
+        let position = *self.index.borrow().get(&MapKey::from(key)?)?;
+        Some(self.entries.borrow()[position].1.clone())
+    }
+
+    // Returns false if the key can't be used in a map
+    pub fn insert(&self, key: Object, value: Object) -> bool {
+        let map_key = if let Some(k) = MapKey::from(&key) {
+            k
+        } else {
+            return false;
+        };
+
+        let existing = self.index.borrow().get(&map_key).copied();
+        if let Some(position) = existing {
+            self.entries.borrow_mut()[position].1 = value;
+        } else {
+            let mut entries = self.entries.borrow_mut();
+            self.index.borrow_mut().insert(map_key, entries.len());
+            entries.push((key, value));
+        }
+        true
+    }
+
+    pub fn remove(&self, key: &Object) -> Option<Object> {
+        let position = self.index.borrow_mut().remove(&MapKey::from(key)?)?;
+        let (_, value) = self.entries.borrow_mut().remove(position);
+        for p in self.index.borrow_mut().values_mut() {
+            if *p > position {
+                *p -= 1;
+            }
+        }
+        Some(value)
+    }
+
+    pub fn contains(&self, key: &Object) -> bool {
+        match MapKey::from(key) {
+            Some(k) => self.index.borrow().contains_key(&k),
+            None => false,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.borrow().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.borrow().is_empty()
+    }
+
+    pub fn keys(&self) -> Vec<Object> {
+        self.entries
+            .borrow()
+            .iter()
+            .map(|(k, _)| k.clone())
+            .collect()
+    }
+
+    // Empties the map, returning what its values were
+    pub fn take_values(&self) -> Vec<Object> {
+        self.index.borrow_mut().clear();
+        self.entries.take().into_iter().map(|(_, v)| v).collect()
+    }
+
+    pub fn values(&self) -> Vec<Object> {
+        self.entries
+            .borrow()
+            .iter()
+            .map(|(_, v)| v.clone())
+            .collect()
+    }
+}
+
+impl PartialEq for LoxMap {
+    fn eq(&self, other: &Self) -> bool {
+        self.len() == other.len()
+            && self
+                .entries
+                .borrow()
+                .iter()
+                .all(|(k, v)| other.get(k).as_ref() == Some(v))
+    }
+}
+
+impl fmt::Display for LoxMap {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        print_once(f, self as *const Self as *const (), "{...}", |f| {
+            let entries = self
+                .entries
+                .borrow()
+                .iter()
+                .map(|(k, v)| format!("{}: {}", k.repr(), v.repr()))
+                .collect::<Vec<String>>()
+                .join(", ");
+            write!(f, "{{{entries}}}")
+        })
+    }
+}
+
+impl Drop for LoxMap {
+    fn drop(&mut self) {
+        release(self.take_values());
+    }
+}
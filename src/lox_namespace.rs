@@ -0,0 +1,55 @@
+use crate::error::*;
+use crate::object::*;
+use crate::token::*;
+use std::collections::HashMap;
+use std::fmt;
+
+// A named group of builtins, like `math`, whose members are read with `.`
+#[derive(Debug)]
+pub struct LoxNamespace {
+    name: String,
+    members: HashMap<String, Object>,
+}
+
+impl LoxNamespace {
+    pub fn new(name: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            members: HashMap::new(),
+        }
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn define(&mut self, name: &str, value: Object) {
+        self.members.insert(name.to_string(), value);
+    }
+
+    pub fn get(&self, name: &Token) -> Result<Object, LoxResult> {
+        self.members.get(&name.as_string()).cloned().ok_or_else(|| {
+            LoxResult::runtime_error(
+                name,
+                &format!(
+                    "Undefined property '{}' on namespace '{}'.",
+                    name.as_string(),
+                    self.name
+                ),
+            )
+        })
+    }
+}
+
+// Namespaces are only created once, when the interpreter starts
+impl PartialEq for LoxNamespace {
+    fn eq(&self, other: &Self) -> bool {
+        std::ptr::eq(self, other)
+    }
+}
+
+impl fmt::Display for LoxNamespace {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "<namespace {}>", self.name)
+    }
+}
@@ -0,0 +1,69 @@
+use std::fmt;
+
+// Ranges are lazy: only the bounds are stored, values are computed on demand
+#[derive(Debug, Clone, PartialEq)]
+pub struct LoxRange {
+    start: f64,
+    end: f64,
+    step: f64,
+    inclusive: bool,
+}
+
+impl LoxRange {
+    // `step` must not be zero; a range whose step points away from its end
+    // is empty
+    pub fn new(start: f64, end: f64, step: f64, inclusive: bool) -> Self {
+        Self {
+            start,
+            end,
+            step,
+            inclusive,
+        }
+    }
+
+    // The number of values, which is infinite for an unbounded range
+    pub fn len(&self) -> f64 {
+        let span = (self.end - self.start) / self.step;
+        let count = if self.inclusive {
+            span.floor() + 1.0
+        } else {
+            span.ceil()
+        };
+        if count > 0.0 {
+            count
+        } else {
+            0.0
+        }
+    }
+
+    pub fn get(&self, index: usize) -> Option<f64> {
+        if (index as f64) < self.len() {
+            Some(self.start + index as f64 * self.step)
+        } else {
+            None
+        }
+    }
+
+    pub fn contains(&self, value: f64) -> bool {
+        let index = (value - self.start) / self.step;
+        index.fract() == 0.0 && index >= 0.0 && index < self.len()
+    }
+
+    pub fn values(&self) -> impl Iterator<Item = f64> + '_ {
+        let len = self.len();
+        (0..)
+            .take_while(move |&i| (i as f64) < len)
+            .map(|i| self.start + i as f64 * self.step)
+    }
+}
+
+impl fmt::Display for LoxRange {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let operator = if self.inclusive { "..=" } else { ".." };
+        write!(f, "{}{operator}{}", self.start, self.end)?;
+        if self.step != 1.0 {
+            write!(f, " step {}", self.step)?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,789 @@
+use std::fmt;
+
+// Counted repetition is compiled by copying the repeated pattern, so both
+// the counts and the size of the compiled program are limited
+const MAX_REPEAT: usize = 1000;
+const MAX_PROGRAM: usize = 100_000;
+
+// Groups are parsed and compiled recursively, so their nesting is limited
+const MAX_DEPTH: usize = 256;
+
+// A compiled regular expression. Matching runs every alternative in step
+// (a Pike VM), so it takes time linear in the text whatever the pattern.
+// Positions are counted in characters, like the rest of the string methods.
+pub struct LoxRegex {
+    pattern: String,
+    flags: Flags,
+    program: Vec<Inst>,
+    groups: usize,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+struct Flags {
+    ignore_case: bool,
+    multiline: bool,
+    dot_all: bool,
+}
+
+#[derive(Debug, Clone)]
+enum Node {
+    Empty,
+    Char(char),
+    Any,
+    Class(CharClass),
+    Assert(Assertion),
+    Group(Box<Node>, Option<usize>),
+    Concat(Vec<Node>),
+    Alternate(Vec<Node>),
+    Repeat {
+        node: Box<Node>,
+        min: usize,
+        max: Option<usize>,
+        greedy: bool,
+        position: usize,
+    },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Assertion {
+    Start,
+    End,
+    WordBoundary,
+    NotWordBoundary,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Perl {
+    Digit,
+    Word,
+    Space,
+}
+
+impl Perl {
+    fn matches(self, c: char) -> bool {
+        match self {
+            Perl::Digit => c.is_ascii_digit(),
+            Perl::Word => is_word(c),
+            Perl::Space => c.is_whitespace(),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+enum ClassItem {
+    Range(char, char),
+    Perl(Perl, bool),
+}
+
+#[derive(Debug, Clone)]
+struct CharClass {
+    negated: bool,
+    items: Vec<ClassItem>,
+}
+
+impl CharClass {
+    fn contains(&self, c: char) -> bool {
+        let found = self.items.iter().any(|item| match *item {
+            ClassItem::Range(low, high) => (low..=high).contains(&c),
+            ClassItem::Perl(perl, negated) => perl.matches(c) != negated,
+        });
+        found != self.negated
+    }
+}
+
+#[derive(Debug, Clone)]
+enum Inst {
+    Char(char),
+    Any,
+    Class(CharClass),
+    Assert(Assertion),
+    // Try the first target before the second
+    Split(usize, usize),
+    Jump(usize),
+    Save(usize),
+    Match,
+}
+
+impl LoxRegex {
+    // `flags` may contain `i` to ignore case, `m` for `^` and `$` to match
+    // at line breaks, and `s` for `.` to match newlines
+    pub fn new(pattern: &str, flags: &str) -> Result<Self, String> {
+        let mut parsed = Flags::default();
+        for flag in flags.chars() {
+            match flag {
+                'i' => parsed.ignore_case = true,
+                'm' => parsed.multiline = true,
+                's' => parsed.dot_all = true,
+                _ => return Err(format!("Unknown regex flag '{flag}'.")),
+            }
+        }
+
+        let mut parser = Parser {
+            chars: pattern.chars().collect(),
+            current: 0,
+            groups: 0,
+            depth: 0,
+        };
+        let node = parser.parse()?;
+        let mut compiler = Compiler {
+            program: vec![Inst::Save(0)],
+            ignore_case: parsed.ignore_case,
+        };
+        compiler.emit(&node)?;
+        compiler.program.push(Inst::Save(1));
+        compiler.program.push(Inst::Match);
+
+        Ok(Self {
+            pattern: pattern.to_string(),
+            flags: parsed,
+            program: compiler.program,
+            groups: parser.groups,
+        })
+    }
+
+    // The number of capture groups, not counting the whole match
+    pub fn groups(&self) -> usize {
+        self.groups
+    }
+
+    // The leftmost match starting at or after `start`, as the span of the
+    // whole match followed by the span of each group, or None for a group
+    // that took no part in the match
+    pub fn find_at(&self, text: &[char], start: usize) -> Option<Vec<Option<(usize, usize)>>> {
+        let slots = 2 * (self.groups + 1);
+        let mut current = Threads::new(self.program.len());
+        let mut next = Threads::new(self.program.len());
+        let mut matched = None;
+
+        for pos in start..=text.len() {
+            // A match that starts here ranks below every thread that
+            // started earlier, so it is only tried while nothing has matched
+            if matched.is_none() {
+                self.add_thread(&mut current, text, pos, 0, vec![None; slots]);
+            }
+            if current.list.is_empty() && matched.is_some() {
+                break;
+            }
+
+            for (pc, saved) in current.list.drain(..) {
+                let accepted = match &self.program[pc] {
+                    Inst::Char(c) => text.get(pos).is_some_and(|t| self.fold(*t) == *c),
+                    Inst::Any => text
+                        .get(pos)
+                        .is_some_and(|t| self.flags.dot_all || *t != '\n'),
+                    Inst::Class(class) => text.get(pos).is_some_and(|t| self.in_class(class, *t)),
+                    Inst::Match => {
+                        // Threads after this one have a lower priority
+                        matched = Some(saved);
+                        break;
+                    }
+                    _ => unreachable!("only consuming instructions are queued"),
+                };
+                if accepted {
+                    self.add_thread(&mut next, text, pos + 1, pc + 1, saved);
+                }
+            }
+            current.clear();
+            std::mem::swap(&mut current, &mut next);
+        }
+
+        matched.map(|saved| {
+            saved
+                .chunks(2)
+                .map(|span| match span {
+                    [Some(start), Some(end)] => Some((*start, *end)),
+                    _ => None,
+                })
+                .collect()
+        })
+    }
+
+    // Follows jumps, splits, saves and assertions from `pc` until reaching
+    // instructions that consume a character, queueing them in priority order
+    fn add_thread(
+        &self,
+        threads: &mut Threads,
+        text: &[char],
+        pos: usize,
+        pc: usize,
+        saved: Vec<Option<usize>>,
+    ) {
+        let mut stack = vec![(pc, saved)];
+        while let Some((pc, mut saved)) = stack.pop() {
+            if threads.seen[pc] {
+                continue;
+            }
+            threads.seen[pc] = true;
+            match &self.program[pc] {
+                Inst::Jump(target) => stack.push((*target, saved)),
+                Inst::Split(first, second) => {
+                    stack.push((*second, saved.clone()));
+                    stack.push((*first, saved));
+                }
+                Inst::Save(slot) => {
+                    saved[*slot] = Some(pos);
+                    stack.push((pc + 1, saved));
+                }
+                Inst::Assert(assertion) => {
+                    if self.holds(*assertion, text, pos) {
+                        stack.push((pc + 1, saved));
+                    }
+                }
+                _ => threads.list.push((pc, saved)),
+            }
+        }
+    }
+
+    fn holds(&self, assertion: Assertion, text: &[char], pos: usize) -> bool {
+        let before = pos.checked_sub(1).map(|i| text[i]);
+        let after = text.get(pos).copied();
+        match assertion {
+            Assertion::Start => before.is_none() || (self.flags.multiline && before == Some('\n')),
+            Assertion::End => after.is_none() || (self.flags.multiline && after == Some('\n')),
+            Assertion::WordBoundary | Assertion::NotWordBoundary => {
+                let boundary = before.is_some_and(is_word) != after.is_some_and(is_word);
+                boundary == (assertion == Assertion::WordBoundary)
+            }
+        }
+    }
+
+    fn fold(&self, c: char) -> char {
+        if self.flags.ignore_case {
+            fold_case(c)
+        } else {
+            c
+        }
+    }
+
+    fn in_class(&self, class: &CharClass, c: char) -> bool {
+        if !self.flags.ignore_case {
+            return class.contains(c);
+        }
+        let lower = fold_case(c);
+        let upper = c.to_uppercase().next().unwrap_or(c);
+        if class.negated {
+            class.contains(c) && class.contains(lower) && class.contains(upper)
+        } else {
+            class.contains(c) || class.contains(lower) || class.contains(upper)
+        }
+    }
+}
+
+impl fmt::Display for LoxRegex {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "/{}/", self.pattern)?;
+        for (flag, set) in [
+            ('i', self.flags.ignore_case),
+            ('m', self.flags.multiline),
+            ('s', self.flags.dot_all),
+        ] {
+            if set {
+                write!(f, "{flag}")?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Debug for LoxRegex {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{self}")
+    }
+}
+
+// Regexes are equal when they were compiled from the same pattern and flags
+impl PartialEq for LoxRegex {
+    fn eq(&self, other: &Self) -> bool {
+        self.pattern == other.pattern && self.flags == other.flags
+    }
+}
+
+// The threads waiting to consume the character at one position. Each
+// instruction runs at most once per position; the first thread to reach it
+// has the highest priority and wins.
+struct Threads {
+    list: Vec<(usize, Vec<Option<usize>>)>,
+    seen: Vec<bool>,
+}
+
+impl Threads {
+    fn new(size: usize) -> Self {
+        Self {
+            list: Vec::new(),
+            seen: vec![false; size],
+        }
+    }
+
+    fn clear(&mut self) {
+        self.list.clear();
+        self.seen.iter_mut().for_each(|seen| *seen = false);
+    }
+}
+
+fn is_word(c: char) -> bool {
+    c.is_alphanumeric() || c == '_'
+}
+
+fn fold_case(c: char) -> char {
+    let mut lower = c.to_lowercase();
+    match (lower.next(), lower.next()) {
+        (Some(folded), None) => folded,
+        _ => c,
+    }
+}
+
+struct Parser {
+    chars: Vec<char>,
+    current: usize,
+    groups: usize,
+    depth: usize,
+}
+
+impl Parser {
+    fn error(&self, position: usize, message: &str) -> String {
+        format!("Invalid regex at position {position}: {message}")
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.current).copied()
+    }
+
+    fn is_match(&mut self, c: char) -> bool {
+        if self.peek() == Some(c) {
+            self.current += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn parse(&mut self) -> Result<Node, String> {
+        let node = self.alternation()?;
+        if self.peek().is_some() {
+            // Only an unmatched `)` stops an alternation early
+            return Err(self.error(self.current, "Unmatched ')'."));
+        }
+        Ok(node)
+    }
+
+    fn alternation(&mut self) -> Result<Node, String> {
+        let mut branches = vec![self.concat()?];
+        while self.is_match('|') {
+            branches.push(self.concat()?);
+        }
+        Ok(if branches.len() == 1 {
+            branches.pop().unwrap()
+        } else {
+            Node::Alternate(branches)
+        })
+    }
+
+    fn concat(&mut self) -> Result<Node, String> {
+        let mut nodes = Vec::new();
+        while !matches!(self.peek(), None | Some('|' | ')')) {
+            nodes.push(self.repeat()?);
+        }
+        Ok(match nodes.len() {
+            0 => Node::Empty,
+            1 => nodes.pop().unwrap(),
+            _ => Node::Concat(nodes),
+        })
+    }
+
+    fn repeat(&mut self) -> Result<Node, String> {
+        let node = self.atom()?;
+        let position = self.current;
+        let (min, max) = match self.peek() {
+            Some('*') => (0, None),
+            Some('+') => (1, None),
+            Some('?') => (0, Some(1)),
+            Some('{') => match self.counted()? {
+                Some(counts) => counts,
+                None => return Ok(node),
+            },
+            _ => return Ok(node),
+        };
+        // The quantifier, or the closing brace of a counted one
+        self.current += 1;
+        let greedy = !self.is_match('?');
+
+        if matches!(node, Node::Assert(_)) {
+            return Err(self.error(position, "Nothing to repeat."));
+        }
+        if matches!(self.peek(), Some('*' | '+' | '?')) || self.counted_at(self.current) {
+            return Err(self.error(self.current, "Nothing to repeat."));
+        }
+        Ok(Node::Repeat {
+            node: Box::new(node),
+            min,
+            max,
+            greedy,
+            position,
+        })
+    }
+
+    // Reads `{n}`, `{n,}` or `{n,m}`, leaving the closing brace to be
+    // consumed by the caller. A brace that doesn't start one is a literal.
+    fn counted(&mut self) -> Result<Option<(usize, Option<usize>)>, String> {
+        let start = self.current;
+        if !self.counted_at(start) {
+            return Ok(None);
+        }
+        self.current += 1;
+        let min = self.count()?;
+        let max = if self.is_match(',') {
+            if self.peek() == Some('}') {
+                None
+            } else {
+                Some(self.count()?)
+            }
+        } else {
+            Some(min)
+        };
+        if max.is_some_and(|max| max < min) {
+            return Err(self.error(start, "Repetition range is out of order."));
+        }
+        Ok(Some((min, max)))
+    }
+
+    fn count(&mut self) -> Result<usize, String> {
+        let start = self.current;
+        while self.peek().is_some_and(|c| c.is_ascii_digit()) {
+            self.current += 1;
+        }
+        let digits = self.chars[start..self.current].iter().collect::<String>();
+        match digits.parse::<usize>() {
+            Ok(n) if n <= MAX_REPEAT => Ok(n),
+            _ => Err(self.error(
+                start,
+                &format!("Repetition count must be at most {MAX_REPEAT}."),
+            )),
+        }
+    }
+
+    // Whether a counted repetition like `{2,5}` starts at `position`
+    fn counted_at(&self, position: usize) -> bool {
+        let rest = &self.chars[position.min(self.chars.len())..];
+        let Some(close) = rest.iter().position(|c| *c == '}') else {
+            return false;
+        };
+        if rest.first() != Some(&'{') {
+            return false;
+        }
+        let inner = rest[1..close].iter().collect::<String>();
+        let (min, max) = inner.split_once(',').unwrap_or((&inner, "0"));
+        !min.is_empty()
+            && min.chars().all(|c| c.is_ascii_digit())
+            && max.chars().all(|c| c.is_ascii_digit())
+    }
+
+    fn atom(&mut self) -> Result<Node, String> {
+        let position = self.current;
+        let Some(c) = self.peek() else {
+            return Err(self.error(position, "Expected a pattern."));
+        };
+        self.current += 1;
+        Ok(match c {
+            '(' => {
+                if self.depth == MAX_DEPTH {
+                    return Err(self.error(position, "Groups are nested too deeply."));
+                }
+                let index = if self.is_match('?') {
+                    if !self.is_match(':') {
+                        return Err(self.error(position, "Unknown group syntax '(?'."));
+                    }
+                    None
+                } else {
+                    self.groups += 1;
+                    Some(self.groups)
+                };
+                self.depth += 1;
+                let inner = self.alternation()?;
+                self.depth -= 1;
+                if !self.is_match(')') {
+                    return Err(self.error(position, "Unclosed group."));
+                }
+                Node::Group(Box::new(inner), index)
+            }
+            '[' => Node::Class(self.class(position)?),
+            '.' => Node::Any,
+            '^' => Node::Assert(Assertion::Start),
+            '$' => Node::Assert(Assertion::End),
+            '\\' => match self.escape(position)? {
+                Escape::Char(c) => Node::Char(c),
+                Escape::Perl(perl, negated) => Node::Class(CharClass {
+                    negated: false,
+                    items: vec![ClassItem::Perl(perl, negated)],
+                }),
+                Escape::Assert(assertion) => Node::Assert(assertion),
+            },
+            '*' | '+' | '?' => return Err(self.error(position, "Nothing to repeat.")),
+            '{' if self.counted_at(position) => {
+                return Err(self.error(position, "Nothing to repeat."))
+            }
+            c => Node::Char(c),
+        })
+    }
+
+    // The backslash at `position` has been consumed
+    fn escape(&mut self, position: usize) -> Result<Escape, String> {
+        let Some(c) = self.peek() else {
+            return Err(self.error(position, "Trailing backslash."));
+        };
+        self.current += 1;
+        Ok(match c {
+            'd' => Escape::Perl(Perl::Digit, false),
+            'D' => Escape::Perl(Perl::Digit, true),
+            'w' => Escape::Perl(Perl::Word, false),
+            'W' => Escape::Perl(Perl::Word, true),
+            's' => Escape::Perl(Perl::Space, false),
+            'S' => Escape::Perl(Perl::Space, true),
+            'b' => Escape::Assert(Assertion::WordBoundary),
+            'B' => Escape::Assert(Assertion::NotWordBoundary),
+            'n' => Escape::Char('\n'),
+            'r' => Escape::Char('\r'),
+            't' => Escape::Char('\t'),
+            'f' => Escape::Char('\u{c}'),
+            'v' => Escape::Char('\u{b}'),
+            '0' => Escape::Char('\0'),
+            c if c.is_alphanumeric() => {
+                return Err(self.error(position, &format!("Unknown escape '\\{c}'.")))
+            }
+            c => Escape::Char(c),
+        })
+    }
+
+    // The `[` at `open` has been consumed. A `]` right after it, or after
+    // `[^`, is taken literally.
+    fn class(&mut self, open: usize) -> Result<CharClass, String> {
+        let negated = self.is_match('^');
+        let mut items = Vec::new();
+        loop {
+            let position = self.current;
+            let low = match self.peek() {
+                None => return Err(self.error(open, "Unterminated character class.")),
+                Some(']') if !items.is_empty() => {
+                    self.current += 1;
+                    break;
+                }
+                Some(_) => self.class_atom()?,
+            };
+            let is_range = self.peek() == Some('-')
+                && !matches!(self.chars.get(self.current + 1), None | Some(']'));
+            if !is_range {
+                items.push(match low {
+                    Escape::Char(c) => ClassItem::Range(c, c),
+                    Escape::Perl(perl, negated) => ClassItem::Perl(perl, negated),
+                    Escape::Assert(_) => unreachable!("assertions are rejected in classes"),
+                });
+                continue;
+            }
+            self.current += 1;
+            match (low, self.class_atom()?) {
+                (Escape::Char(low), Escape::Char(high)) if low <= high => {
+                    items.push(ClassItem::Range(low, high))
+                }
+                (Escape::Char(low), Escape::Char(high)) => {
+                    return Err(
+                        self.error(position, &format!("Range '{low}-{high}' is out of order."))
+                    )
+                }
+                _ => return Err(self.error(position, "Invalid range in character class.")),
+            }
+        }
+        Ok(CharClass { negated, items })
+    }
+
+    fn class_atom(&mut self) -> Result<Escape, String> {
+        let position = self.current;
+        let c = self.peek().unwrap();
+        self.current += 1;
+        if c != '\\' {
+            return Ok(Escape::Char(c));
+        }
+        match self.escape(position)? {
+            Escape::Assert(_) => Err(self.error(
+                position,
+                "Word boundaries can't be used in a character class.",
+            )),
+            escape => Ok(escape),
+        }
+    }
+}
+
+enum Escape {
+    Char(char),
+    Perl(Perl, bool),
+    Assert(Assertion),
+}
+
+struct Compiler {
+    program: Vec<Inst>,
+    ignore_case: bool,
+}
+
+impl Compiler {
+    fn emit(&mut self, node: &Node) -> Result<(), String> {
+        match node {
+            Node::Empty => {}
+            Node::Char(c) if self.ignore_case => self.program.push(Inst::Char(fold_case(*c))),
+            Node::Char(c) => self.program.push(Inst::Char(*c)),
+            Node::Any => self.program.push(Inst::Any),
+            Node::Class(class) => self.program.push(Inst::Class(class.clone())),
+            Node::Assert(assertion) => self.program.push(Inst::Assert(*assertion)),
+            Node::Group(inner, Some(index)) => {
+                self.program.push(Inst::Save(2 * index));
+                self.emit(inner)?;
+                self.program.push(Inst::Save(2 * index + 1));
+            }
+            Node::Group(inner, None) => self.emit(inner)?,
+            Node::Concat(nodes) => {
+                for node in nodes {
+                    self.emit(node)?;
+                }
+            }
+            Node::Alternate(branches) => {
+                let mut jumps = Vec::new();
+                for (i, branch) in branches.iter().enumerate() {
+                    if i == branches.len() - 1 {
+                        self.emit(branch)?;
+                        break;
+                    }
+                    let split = self.placeholder();
+                    self.emit(branch)?;
+                    jumps.push(self.placeholder());
+                    self.program[split] = Inst::Split(split + 1, self.program.len());
+                }
+                let end = self.program.len();
+                for jump in jumps {
+                    self.program[jump] = Inst::Jump(end);
+                }
+            }
+            Node::Repeat {
+                node,
+                min,
+                max,
+                greedy,
+                position,
+            } => {
+                for _ in 0..*min {
+                    self.emit(node)?;
+                }
+                match max {
+                    None => {
+                        let split = self.placeholder();
+                        self.emit(node)?;
+                        self.program.push(Inst::Jump(split));
+                        self.patch_split(split, *greedy);
+                    }
+                    Some(max) => {
+                        // Each optional copy is nested in the one before, so
+                        // skipping one skips the rest
+                        let mut splits = Vec::new();
+                        for _ in *min..*max {
+                            splits.push(self.placeholder());
+                            self.emit(node)?;
+                        }
+                        for split in splits {
+                            self.patch_split(split, *greedy);
+                        }
+                    }
+                }
+                if self.program.len() > MAX_PROGRAM {
+                    return Err(format!(
+                        "Invalid regex at position {position}: Repetition makes the pattern too large."
+                    ));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn placeholder(&mut self) -> usize {
+        self.program.push(Inst::Jump(0));
+        self.program.len() - 1
+    }
+
+    // Points the split at `split` into the repeated code that follows it
+    // and past the end of the program so far, preferring one by `greedy`
+    fn patch_split(&mut self, split: usize, greedy: bool) {
+        let (body, end) = (split + 1, self.program.len());
+        self.program[split] = if greedy {
+            Inst::Split(body, end)
+        } else {
+            Inst::Split(end, body)
+        };
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // The text of the whole match and of each group
+    fn find(pattern: &str, text: &str) -> Option<Vec<Option<String>>> {
+        let regex = LoxRegex::new(pattern, "").unwrap();
+        let text = text.chars().collect::<Vec<char>>();
+        let spans = regex.find_at(&text, 0)?;
+        Some(
+            spans
+                .into_iter()
+                .map(|span| span.map(|(start, end)| text[start..end].iter().collect()))
+                .collect(),
+        )
+    }
+
+    fn whole(pattern: &str, text: &str) -> Option<String> {
+        find(pattern, text).and_then(|spans| spans[0].clone())
+    }
+
+    #[test]
+    fn greedy_repetition_takes_the_most() {
+        assert_eq!(whole("<.*>", "<a><b>").as_deref(), Some("<a><b>"));
+        assert_eq!(whole("a+", "caaat").as_deref(), Some("aaa"));
+        assert_eq!(whole("a{2,3}", "aaaa").as_deref(), Some("aaa"));
+        assert_eq!(whole("ab?", "abc").as_deref(), Some("ab"));
+    }
+
+    #[test]
+    fn lazy_repetition_takes_the_least() {
+        assert_eq!(whole("<.*?>", "<a><b>").as_deref(), Some("<a>"));
+        assert_eq!(whole("a+?", "caaat").as_deref(), Some("a"));
+        assert_eq!(whole("a{2,3}?", "aaaa").as_deref(), Some("aa"));
+        assert_eq!(whole("ab??", "abc").as_deref(), Some("a"));
+        assert_eq!(whole("a.*?t", "a cat sat").as_deref(), Some("a cat"));
+    }
+
+    #[test]
+    fn alternation_prefers_earlier_branches() {
+        assert_eq!(whole("a|ab", "ab").as_deref(), Some("a"));
+        assert_eq!(whole("ab|a", "ab").as_deref(), Some("ab"));
+        assert_eq!(whole("(?:|x)y", "xy").as_deref(), Some("xy"));
+        assert_eq!(whole("cat|dog", "hotdog cat").as_deref(), Some("dog"));
+    }
+
+    #[test]
+    fn groups_report_their_last_iteration() {
+        let spans = find("(a|(b))+", "ab").unwrap();
+        assert_eq!(spans[0].as_deref(), Some("ab"));
+        assert_eq!(spans[1].as_deref(), Some("b"));
+        assert_eq!(spans[2].as_deref(), Some("b"));
+        assert_eq!(find("(x)?y", "y").unwrap()[1], None);
+    }
+
+    #[test]
+    fn invalid_patterns_give_their_position() {
+        let error = |pattern: &str| LoxRegex::new(pattern, "").err().unwrap();
+        assert_eq!(error("a(b"), "Invalid regex at position 1: Unclosed group.");
+        assert_eq!(error("a)"), "Invalid regex at position 1: Unmatched ')'.");
+        assert_eq!(
+            error("*a"),
+            "Invalid regex at position 0: Nothing to repeat."
+        );
+        let deep = "(".repeat(MAX_DEPTH + 1) + &")".repeat(MAX_DEPTH + 1);
+        assert_eq!(
+            error(&deep),
+            format!("Invalid regex at position {MAX_DEPTH}: Groups are nested too deeply.")
+        );
+    }
+}
@@ -0,0 +1,56 @@
+use crate::object::*;
+use std::collections::HashMap;
+use std::fmt;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct LoxTrait {
+    name: String,
+    methods: HashMap<String, Object>,
+    abstract_methods: Vec<String>,
+}
+
+impl LoxTrait {
+    pub fn new(
+        name: &str,
+        methods: HashMap<String, Object>,
+        abstract_methods: Vec<String>,
+    ) -> Self {
+        Self {
+            name: name.to_string(),
+            methods,
+            abstract_methods,
+        }
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn abstract_methods(&self) -> &[String] {
+        &self.abstract_methods
+    }
+
+    // Sorted by name, so conflicts between traits are always reported the same way
+    pub fn methods(&self) -> Vec<(String, Object)> {
+        let mut methods = self
+            .methods
+            .iter()
+            .map(|(k, v)| (k.clone(), v.clone()))
+            .collect::<Vec<(String, Object)>>();
+        methods.sort_by(|a, b| a.0.cmp(&b.0));
+        methods
+    }
+}
+
+impl fmt::Display for LoxTrait {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let methods = self
+            .methods()
+            .into_iter()
+            .map(|(name, _)| name)
+            .filter(|name| !name.starts_with('#'))
+            .collect::<Vec<String>>()
+            .join(", ");
+        write!(f, "<Trait {} {{ {methods} }}>", self.name)
+    }
+}
@@ -12,7 +12,11 @@ mod callable;
 mod environment;
 mod expr;
 mod lox_function;
+mod native_datetime;
+mod native_format;
 mod native_functions;
+mod native_io;
+mod native_json;
 mod resolver;
 mod stmt;
 
@@ -20,25 +24,55 @@ mod interpreter;
 use interpreter::*;
 use resolver::*;
 mod object;
+mod pattern;
+mod pattern_matcher;
 use std::rc::Rc;
 mod lox_class;
+mod lox_datetime;
+mod lox_enum;
+mod lox_generator;
 mod lox_instance;
+mod lox_iterator;
+mod lox_list;
+mod lox_map;
+mod lox_namespace;
+mod lox_range;
+mod lox_regex;
+mod lox_trait;
+mod native_math;
+mod native_methods;
+mod native_regex;
+mod random;
 
 pub fn main() {
-    let args: Vec<String> = args().collect();
+    let mut args: Vec<String> = args().collect();
     //println!("args: {:?}", args);
     let lox = Lox::new();
 
+    // `--seed n` makes the random builtins repeat the same sequence
+    if args.get(1).is_some_and(|arg| arg == "--seed") {
+        match args.get(2).map(|seed| seed.parse::<u64>()) {
+            Some(Ok(seed)) => lox.interpreter.seed_random(seed),
+            _ => usage(),
+        }
+        args.drain(1..3);
+    }
+
+    // Anything after the script is passed on to it, see `args()`
     match args.len() {
         1 => lox.run_prompt(),
-        2 => lox.run_file(&args[1]).expect("Could not run file"),
         _ => {
-            println!("Usage: lox-ast [script]");
-            std::process::exit(64);
+            lox.interpreter.set_script_args(args.split_off(2));
+            lox.run_file(&args[1]).expect("Could not run file")
         }
     }
 }
 
+fn usage() -> ! {
+    println!("Usage: lox-ast [--seed n] [script [arguments...]]");
+    std::process::exit(64);
+}
+
 struct Lox {
     interpreter: Interpreter,
 }
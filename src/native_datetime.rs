@@ -0,0 +1,475 @@
+use crate::error::*;
+use crate::lox_datetime::*;
+use crate::lox_namespace::*;
+use crate::native_functions::*;
+use crate::native_methods::*;
+use crate::object::*;
+use crate::token::*;
+use std::rc::Rc;
+use std::time::SystemTime;
+
+const UNITS: [&str; 5] = ["days", "hours", "minutes", "seconds", "millis"];
+
+pub fn datetime_namespace() -> LoxNamespace {
+    let mut datetime = LoxNamespace::new("datetime");
+
+    // The current time, in UTC unless an offset in minutes is given
+    define(&mut datetime, "now", 0, 1, |args| {
+        let millis = SystemTime::now()
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .map_or(0, |d| d.as_millis() as i64);
+        let offset = match args.first() {
+            Some(offset) => offset_arg("datetime.now", offset)?,
+            None => 0,
+        };
+        LoxDateTime::from_timestamp(millis, offset)
+            .map(datetime_object)
+            .map_err(|e| LoxResult::native_error(&e))
+    });
+
+    // `of(year, month, day, hour, minute, second, millisecond)` in UTC, where
+    // everything after the day is optional
+    define(&mut datetime, "of", 3, 4, |args| {
+        let mut fields = [0u32; 6];
+        for (field, arg) in fields.iter_mut().zip(&args[1..]) {
+            *field = integer_arg("datetime.of", arg)?.try_into().map_err(|_| {
+                LoxResult::native_error("'datetime.of' fields must not be negative.")
+            })?;
+        }
+        let civil = Civil {
+            year: integer_arg("datetime.of", &args[0])?,
+            month: fields[0],
+            day: fields[1],
+            hour: fields[2],
+            minute: fields[3],
+            second: fields[4],
+            millisecond: fields[5],
+        };
+        LoxDateTime::from_civil(&civil, 0)
+            .map(datetime_object)
+            .map_err(|e| LoxResult::native_error(&e))
+    });
+
+    define(&mut datetime, "fromTimestamp", 1, 0, |args| {
+        let millis = integer_arg("datetime.fromTimestamp", &args[0])?;
+        LoxDateTime::from_timestamp(millis, 0)
+            .map(datetime_object)
+            .map_err(|e| LoxResult::native_error(&e))
+    });
+
+    // ISO-8601 text, or text in the layout of a `format` pattern
+    define(&mut datetime, "parse", 1, 1, |args| {
+        let text = string_arg("datetime.parse", &args[0])?;
+        let parsed = match args.get(1) {
+            Some(pattern) => parse_pattern(text, string_arg("datetime.parse", pattern)?),
+            None => parse_iso(text),
+        };
+        parsed
+            .map(datetime_object)
+            .map_err(|e| LoxResult::native_error(&e))
+    });
+
+    for unit in UNITS {
+        define(&mut datetime, unit, 1, 0, move |args| {
+            let amount = number_arg(&format!("datetime.{unit}"), &args[0])?;
+            LoxDuration::from_unit(amount, unit)
+                .map(duration_object)
+                .map_err(|e| LoxResult::native_error(&e))
+        });
+    }
+
+    define(&mut datetime, "isLeapYear", 1, 0, |args| {
+        Ok(Object::Bool(is_leap_year(integer_arg(
+            "datetime.isLeapYear",
+            &args[0],
+        )?)))
+    });
+
+    datetime
+}
+
+fn define(
+    namespace: &mut LoxNamespace,
+    name: &str,
+    arity: usize,
+    optional: usize,
+    func: impl Fn(Vec<Object>) -> Result<Object, LoxResult> + 'static,
+) {
+    let native = NativeFunction::with_optional(
+        &format!("{}.{name}", namespace.name()),
+        arity,
+        optional,
+        move |_, args| func(args),
+    );
+    namespace.define(name, Object::Native(Rc::new(native)));
+}
+
+fn datetime_object(datetime: LoxDateTime) -> Object {
+    Object::DateTime(Rc::new(datetime))
+}
+
+fn duration_object(duration: LoxDuration) -> Object {
+    Object::Duration(Rc::new(duration))
+}
+
+fn offset_arg(method: &str, arg: &Object) -> Result<i32, LoxResult> {
+    let offset = integer_arg(method, arg)?;
+    i32::try_from(offset).map_err(|_| {
+        LoxResult::native_error(&format!("UTC offset of {offset} minutes is out of range."))
+    })
+}
+
+// Calendar fields are properties, like `d.year`; the rest are methods
+pub fn datetime_method(datetime: &Rc<LoxDateTime>, name: &Token) -> Result<Object, LoxResult> {
+    let dt = **datetime;
+    let method = name.as_string();
+    let civil = dt.civil();
+    let field = match method.as_str() {
+        "year" => Some(civil.year as f64),
+        "month" => Some(civil.month as f64),
+        "day" => Some(civil.day as f64),
+        "hour" => Some(civil.hour as f64),
+        "minute" => Some(civil.minute as f64),
+        "second" => Some(civil.second as f64),
+        "millisecond" => Some(civil.millisecond as f64),
+        "weekday" => Some(dt.weekday() as f64),
+        "dayOfYear" => Some(dt.day_of_year() as f64),
+        "offset" => Some(dt.offset() as f64),
+        "timestamp" => Some(dt.timestamp() as f64),
+        _ => None,
+    };
+    if let Some(value) = field {
+        return Ok(Object::Num(value));
+    }
+    if method == "weekdayName" {
+        return Ok(Object::Str(WEEKDAYS[dt.weekday() as usize - 1].to_string()));
+    }
+    let native = match method.as_str() {
+        "withOffset" => NativeFunction::new("withOffset", 1, move |_, args| {
+            dt.with_offset(offset_arg("withOffset", &args[0])?)
+                .map(datetime_object)
+                .map_err(|e| LoxResult::native_error(&e))
+        }),
+        "toUtc" => NativeFunction::new("toUtc", 0, move |_, _| {
+            dt.with_offset(0)
+                .map(datetime_object)
+                .map_err(|e| LoxResult::native_error(&e))
+        }),
+        "format" => NativeFunction::new("format", 1, move |_, args| {
+            format_pattern(&dt, string_arg("format", &args[0])?)
+                .map(Object::Str)
+                .map_err(|e| LoxResult::native_error(&e))
+        }),
+        "toIso" => NativeFunction::new("toIso", 0, move |_, _| Ok(Object::Str(dt.to_string()))),
+        "add" => NativeFunction::new("add", 1, move |_, args| match &args[0] {
+            Object::Duration(duration) => dt
+                .plus(duration)
+                .map(datetime_object)
+                .map_err(|e| LoxResult::native_error(&e)),
+            other => Err(LoxResult::native_error(&format!(
+                "'add' expects a duration, not '{}'.",
+                other.type_name()
+            ))),
+        }),
+        // The start of the same day, at the same offset
+        "date" => NativeFunction::new("date", 0, move |_, _| {
+            let civil = Civil::date(civil.year, civil.month, civil.day);
+            LoxDateTime::from_civil(&civil, dt.offset())
+                .map(datetime_object)
+                .map_err(|e| LoxResult::native_error(&e))
+        }),
+        _ => return undefined(name, "datetime"),
+    };
+    Ok(Object::Native(Rc::new(native)))
+}
+
+// Totals like `d.totalSeconds` are properties; `abs` is a method
+pub fn duration_method(duration: &Rc<LoxDuration>, name: &Token) -> Result<Object, LoxResult> {
+    let duration = **duration;
+    let method = name.as_string();
+    if let Some(unit) = method.strip_prefix("total") {
+        let unit = unit.to_lowercase();
+        if let Some(amount) = duration.in_unit(&unit) {
+            return Ok(Object::Num(amount));
+        }
+    }
+    let native = match method.as_str() {
+        "abs" => NativeFunction::new("abs", 0, move |_, _| Ok(duration_object(duration.abs()))),
+        _ => return undefined(name, "duration"),
+    };
+    Ok(Object::Native(Rc::new(native)))
+}
+
+// Formats with strftime-like directives: %Y year, %y two-digit year, %m
+// month, %d day, %e space-padded day, %H hour, %I 12-hour hour, %p AM/PM,
+// %M minute, %S second, %f millisecond, %j day of year, %u weekday number,
+// %a/%A weekday name, %b/%B month name, %z offset as +hhmm, %:z as +hh:mm,
+// %Z UTC or the offset, %F for %Y-%m-%d, %T for %H:%M:%S, %% a percent sign
+pub fn format_pattern(dt: &LoxDateTime, pattern: &str) -> Result<String, String> {
+    let c = dt.civil();
+    let mut out = String::new();
+    let mut chars = pattern.chars();
+    while let Some(ch) = chars.next() {
+        if ch != '%' {
+            out.push(ch);
+            continue;
+        }
+        let directive = match chars.next() {
+            Some(':') if chars.next() == Some('z') => ":z".to_string(),
+            Some(d) => d.to_string(),
+            None => return Err("Format pattern ends with a lone '%'.".to_string()),
+        };
+        let hour12 = match c.hour % 12 {
+            0 => 12,
+            h => h,
+        };
+        let text = match directive.as_str() {
+            "Y" => format_year(c.year),
+            "y" => format!("{:02}", c.year.rem_euclid(100)),
+            "m" => format!("{:02}", c.month),
+            "d" => format!("{:02}", c.day),
+            "e" => format!("{:2}", c.day),
+            "H" => format!("{:02}", c.hour),
+            "I" => format!("{hour12:02}"),
+            "p" => (if c.hour < 12 { "AM" } else { "PM" }).to_string(),
+            "M" => format!("{:02}", c.minute),
+            "S" => format!("{:02}", c.second),
+            "f" => format!("{:03}", c.millisecond),
+            "j" => format!("{:03}", dt.day_of_year()),
+            "u" => dt.weekday().to_string(),
+            "a" => WEEKDAYS[dt.weekday() as usize - 1][..3].to_string(),
+            "A" => WEEKDAYS[dt.weekday() as usize - 1].to_string(),
+            "b" => MONTHS[c.month as usize - 1][..3].to_string(),
+            "B" => MONTHS[c.month as usize - 1].to_string(),
+            "z" => format_offset(dt.offset(), false),
+            ":z" => format_offset(dt.offset(), true),
+            "Z" if dt.offset() == 0 => "UTC".to_string(),
+            "Z" => format_offset(dt.offset(), true),
+            "F" => format_pattern(dt, "%Y-%m-%d")?,
+            "T" => format_pattern(dt, "%H:%M:%S")?,
+            "%" => "%".to_string(),
+            other => return Err(format!("Unknown format directive '%{other}'.")),
+        };
+        out.push_str(&text);
+    }
+    Ok(out)
+}
+
+// Reads text one character at a time, keeping the position for errors
+struct Scanner<'a> {
+    text: &'a str,
+    chars: Vec<char>,
+    current: usize,
+}
+
+impl<'a> Scanner<'a> {
+    fn new(text: &'a str) -> Self {
+        Self {
+            text,
+            chars: text.chars().collect(),
+            current: 0,
+        }
+    }
+
+    fn error(&self, expected: &str) -> String {
+        format!(
+            "Can't parse '{}' as a date: expected {expected} at position {}.",
+            self.text, self.current
+        )
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.current).copied()
+    }
+
+    fn is_match(&mut self, c: char) -> bool {
+        if self.peek() == Some(c) {
+            self.current += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expect(&mut self, c: char) -> Result<(), String> {
+        if self.is_match(c) {
+            Ok(())
+        } else {
+            Err(self.error(&format!("'{c}'")))
+        }
+    }
+
+    // Between `min` and `max` digits
+    fn number(&mut self, min: usize, max: usize, what: &str) -> Result<u32, String> {
+        let start = self.current;
+        let mut value = 0u32;
+        while self.current - start < max {
+            match self.peek().and_then(|c| c.to_digit(10)) {
+                Some(digit) => {
+                    value = value * 10 + digit;
+                    self.current += 1;
+                }
+                None => break,
+            }
+        }
+        if self.current - start < min {
+            self.current = start;
+            return Err(self.error(what));
+        }
+        Ok(value)
+    }
+
+    fn year(&mut self) -> Result<i64, String> {
+        let negative = self.is_match('-');
+        if !negative {
+            self.is_match('+');
+        }
+        let year = self.number(4, 6, "a four-digit year")? as i64;
+        Ok(if negative { -year } else { year })
+    }
+
+    // `Z`, `UTC`, `+hh:mm`, `+hhmm` or `+hh`
+    fn offset(&mut self) -> Result<i32, String> {
+        // `UTC` is how `%Z` formats a zero offset
+        if self.chars[self.current..].starts_with(&['U', 'T', 'C']) {
+            self.current += 3;
+            return Ok(0);
+        }
+        if self.is_match('Z') {
+            return Ok(0);
+        }
+        let sign = if self.is_match('+') {
+            1
+        } else if self.is_match('-') {
+            -1
+        } else {
+            return Err(self.error("'Z', 'UTC' or a UTC offset"));
+        };
+        let hours = self.number(2, 2, "offset hours")? as i32;
+        let minutes = if self.is_match(':') || self.peek().is_some_and(|c| c.is_ascii_digit()) {
+            self.number(2, 2, "offset minutes")? as i32
+        } else {
+            0
+        };
+        Ok(sign * (hours * 60 + minutes))
+    }
+
+    // A month or weekday name, in full or abbreviated to three letters
+    fn name(&mut self, names: &[&str], what: &str) -> Result<usize, String> {
+        let rest = self.chars[self.current..].iter().collect::<String>();
+        for (i, name) in names.iter().enumerate() {
+            for candidate in [*name, name.get(..3).unwrap_or(name)] {
+                let matched = rest
+                    .get(..candidate.len())
+                    .is_some_and(|prefix| prefix.eq_ignore_ascii_case(candidate));
+                if matched {
+                    self.current += candidate.chars().count();
+                    return Ok(i);
+                }
+            }
+        }
+        Err(self.error(what))
+    }
+
+    fn finish(&self) -> Result<(), String> {
+        if self.current == self.chars.len() {
+            Ok(())
+        } else {
+            Err(self.error("the end of the text"))
+        }
+    }
+}
+
+// `2024-03-05`, `2024-03-05T09:30`, `2024-03-05 09:30:15.250+02:00`, ...
+// Without an offset the time is taken to be UTC.
+pub fn parse_iso(text: &str) -> Result<LoxDateTime, String> {
+    let mut scanner = Scanner::new(text);
+    let mut civil = Civil::date(scanner.year()?, 1, 1);
+    scanner.expect('-')?;
+    civil.month = scanner.number(2, 2, "a two-digit month")?;
+    scanner.expect('-')?;
+    civil.day = scanner.number(2, 2, "a two-digit day")?;
+
+    let mut offset = 0;
+    if scanner.is_match('T') || scanner.is_match(' ') {
+        civil.hour = scanner.number(2, 2, "a two-digit hour")?;
+        scanner.expect(':')?;
+        civil.minute = scanner.number(2, 2, "two-digit minutes")?;
+        if scanner.is_match(':') {
+            civil.second = scanner.number(2, 2, "two-digit seconds")?;
+            if scanner.is_match('.') {
+                civil.millisecond = fraction_millis(&mut scanner)?;
+            }
+        }
+        if scanner.peek().is_some() {
+            offset = scanner.offset()?;
+        }
+    }
+    scanner.finish()?;
+    LoxDateTime::from_civil(&civil, offset)
+}
+
+// Any number of fractional digits, of which only milliseconds are kept
+fn fraction_millis(scanner: &mut Scanner) -> Result<u32, String> {
+    let start = scanner.current;
+    let digits = scanner.number(1, 9, "fractional seconds")?;
+    let count = (scanner.current - start) as u32;
+    Ok(if count >= 3 {
+        digits / 10u32.pow(count - 3)
+    } else {
+        digits * 10u32.pow(3 - count)
+    })
+}
+
+// Parses text laid out like a `format_pattern`
+pub fn parse_pattern(text: &str, pattern: &str) -> Result<LoxDateTime, String> {
+    let pattern = pattern.replace("%F", "%Y-%m-%d").replace("%T", "%H:%M:%S");
+    let mut scanner = Scanner::new(text);
+    let mut civil = Civil::date(1970, 1, 1);
+    let mut offset = 0;
+    let mut pm = None;
+    let mut hour12 = None;
+
+    let mut directives = pattern.chars();
+    while let Some(ch) = directives.next() {
+        if ch != '%' {
+            scanner.expect(ch)?;
+            continue;
+        }
+        match directives.next() {
+            Some('Y') => civil.year = scanner.year()?,
+            Some('y') => civil.year = 2000 + scanner.number(2, 2, "a two-digit year")? as i64,
+            Some('m') => civil.month = scanner.number(1, 2, "a month")?,
+            Some('d' | 'e') => {
+                scanner.is_match(' ');
+                civil.day = scanner.number(1, 2, "a day")?;
+            }
+            Some('H') => civil.hour = scanner.number(1, 2, "an hour")?,
+            Some('I') => hour12 = Some(scanner.number(1, 2, "an hour")?),
+            Some('M') => civil.minute = scanner.number(1, 2, "minutes")?,
+            Some('S') => civil.second = scanner.number(1, 2, "seconds")?,
+            Some('f') => civil.millisecond = fraction_millis(&mut scanner)?,
+            Some('p') => {
+                pm = Some(scanner.name(&["PM", "AM"], "'AM' or 'PM'")? == 0);
+            }
+            Some('b' | 'B') => civil.month = scanner.name(&MONTHS, "a month name")? as u32 + 1,
+            Some('a' | 'A') => {
+                scanner.name(&WEEKDAYS, "a weekday name")?;
+            }
+            Some('z' | 'Z') => offset = scanner.offset()?,
+            Some(':') if directives.next() == Some('z') => offset = scanner.offset()?,
+            Some('%') => scanner.expect('%')?,
+            Some(other) => return Err(format!("Can't parse with format directive '%{other}'.")),
+            None => return Err("Format pattern ends with a lone '%'.".to_string()),
+        }
+    }
+    scanner.finish()?;
+
+    if let Some(hour) = hour12 {
+        if !(1..=12).contains(&hour) {
+            return Err(format!("Hour {hour} is out of range 1 to 12."));
+        }
+        civil.hour = hour % 12 + if pm == Some(true) { 12 } else { 0 };
+    }
+    LoxDateTime::from_civil(&civil, offset)
+}
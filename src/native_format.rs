@@ -0,0 +1,185 @@
+use crate::error::*;
+use crate::interpreter::*;
+use crate::object::*;
+use std::io::{self, Write};
+use std::iter::Peekable;
+use std::str::Chars;
+
+// `format("{} is {:>8.2}", name, value)` fills each `{}` with the next
+// argument, or `{n}` with argument n. After a `:` comes an optional fill
+// character and alignment (`<`, `>` or `^`), a width, and a precision, which
+// is the number of decimals for numbers and the most characters for anything
+// else. `{{` and `}}` stand for the braces themselves.
+pub fn format(_: &Interpreter, mut arguments: Vec<Object>) -> Result<Object, LoxResult> {
+    let Object::Str(template) = arguments.remove(0) else {
+        return Err(LoxResult::native_error(
+            "format() expects a string template as its first argument.",
+        ));
+    };
+    format_template(&template, &arguments).map(Object::Str)
+}
+
+// Prints its arguments one after another, without a newline
+pub fn write(_: &Interpreter, arguments: Vec<Object>) -> Result<Object, LoxResult> {
+    let mut stdout = io::stdout().lock();
+    for argument in &arguments {
+        write!(stdout, "{argument}")
+            .map_err(|e| LoxResult::native_error(&format!("Can't write: {e}.")))?;
+    }
+    let _ = stdout.flush();
+    Ok(Object::Nil)
+}
+
+fn format_template(template: &str, arguments: &[Object]) -> Result<String, LoxResult> {
+    let mut out = String::new();
+    let mut chars = template.chars().peekable();
+    let mut next = 0;
+    let mut used = vec![false; arguments.len()];
+
+    while let Some(c) = chars.next() {
+        match c {
+            '{' if chars.peek() == Some(&'{') => {
+                chars.next();
+                out.push('{');
+            }
+            '}' if chars.peek() == Some(&'}') => {
+                chars.next();
+                out.push('}');
+            }
+            '}' => return Err(format_error("has an unmatched '}'.")),
+            '{' => {
+                let (index, spec) = placeholder(&mut chars)?;
+                let index = index.unwrap_or_else(|| {
+                    next += 1;
+                    next - 1
+                });
+                let Some(argument) = arguments.get(index) else {
+                    let given = match arguments.len() {
+                        0 => "none were given".to_string(),
+                        1 => "only 1 was given".to_string(),
+                        n => format!("only {n} were given"),
+                    };
+                    return Err(format_error(&format!(
+                        "uses argument {index}, but {given}."
+                    )));
+                };
+                used[index] = true;
+                out.push_str(&spec.apply(argument));
+            }
+            c => out.push(c),
+        }
+    }
+
+    match used.iter().filter(|u| !**u).count() {
+        0 => Ok(out),
+        unused => Err(format_error(&format!(
+            "doesn't use {unused} of the {} arguments.",
+            arguments.len()
+        ))),
+    }
+}
+
+fn format_error(message: &str) -> LoxResult {
+    LoxResult::native_error(&format!("format() template {message}"))
+}
+
+#[derive(Default)]
+struct Spec {
+    fill: Option<char>,
+    align: Option<char>,
+    width: usize,
+    precision: Option<usize>,
+}
+
+impl Spec {
+    fn apply(&self, value: &Object) -> String {
+        let text = match (value, self.precision) {
+            (Object::Num(n), Some(precision)) => format!("{n:.precision$}"),
+            (_, Some(precision)) => value.to_string().chars().take(precision).collect(),
+            (_, None) => value.to_string(),
+        };
+
+        let padding = self.width.saturating_sub(text.chars().count());
+        let fill = self.fill.unwrap_or(' ');
+        let default_align = if matches!(value, Object::Num(_)) {
+            '>'
+        } else {
+            '<'
+        };
+        let (before, after) = match self.align.unwrap_or(default_align) {
+            '>' => (padding, 0),
+            '^' => (padding / 2, padding - padding / 2),
+            _ => (0, padding),
+        };
+        let pad = |n: usize| fill.to_string().repeat(n);
+        format!("{}{text}{}", pad(before), pad(after))
+    }
+}
+
+// Reads what follows a `{` up to and including the closing `}`
+fn placeholder(chars: &mut Peekable<Chars>) -> Result<(Option<usize>, Spec), LoxResult> {
+    let mut inside = String::new();
+    loop {
+        match chars.next() {
+            Some('}') => break,
+            Some(c) => inside.push(c),
+            None => return Err(format_error("has an unterminated '{'.")),
+        }
+    }
+
+    let (index, spec) = inside.split_once(':').unwrap_or((&inside, ""));
+    let index = match index {
+        "" => None,
+        index => Some(
+            index
+                .parse::<usize>()
+                .map_err(|_| format_error(&format!("has an invalid argument index '{index}'.")))?,
+        ),
+    };
+    Ok((index, parse_spec(spec)?))
+}
+
+fn parse_spec(spec: &str) -> Result<Spec, LoxResult> {
+    let mut result = Spec::default();
+    let chars = spec.chars().collect::<Vec<char>>();
+    let mut i = 0;
+
+    if chars.len() >= 2 && matches!(chars[1], '<' | '>' | '^') {
+        result.fill = Some(chars[0]);
+        result.align = Some(chars[1]);
+        i = 2;
+    } else if chars.first().is_some_and(|c| matches!(c, '<' | '>' | '^')) {
+        result.align = Some(chars[0]);
+        i = 1;
+    }
+
+    let rest = chars[i..].iter().collect::<String>();
+    let (width, precision) = match rest.split_once('.') {
+        Some((width, precision)) => (width, Some(precision)),
+        None => (rest.as_str(), None),
+    };
+    if !width.is_empty() {
+        result.width = limited(spec, width, "width")?;
+    }
+    if let Some(precision) = precision {
+        result.precision = Some(limited(spec, precision, "precision")?);
+    }
+    Ok(result)
+}
+
+// Width and precision are capped where Rust's own formatter caps them
+fn limited(spec: &str, digits: &str, what: &str) -> Result<usize, LoxResult> {
+    if digits.is_empty() || !digits.chars().all(|c| c.is_ascii_digit()) {
+        return Err(invalid_spec(spec));
+    }
+    digits.parse::<u16>().map(usize::from).map_err(|_| {
+        format_error(&format!(
+            "has a {what} of {digits}, but it can be at most {}.",
+            u16::MAX
+        ))
+    })
+}
+
+fn invalid_spec(spec: &str) -> LoxResult {
+    format_error(&format!("has an invalid specifier '{spec}'."))
+}
@@ -1,52 +1,368 @@
 use crate::callable::*;
+use crate::environment::*;
 use crate::error::*;
 use crate::interpreter::*;
 use crate::lox_class::*;
+use crate::lox_list::*;
+use crate::lox_namespace::*;
+use crate::native_format::*;
+use crate::native_io::*;
+use crate::native_methods::*;
 use crate::object::*;
 use std::fmt;
+use std::io::{self, Write};
 use std::rc::Rc;
-use std::time::SystemTime;
-pub struct NativeClock;
+use std::time::{Duration, SystemTime};
 
-impl fmt::Display for NativeClock {
+type NativeFn = dyn Fn(&Interpreter, Vec<Object>) -> Result<Object, LoxResult>;
+
+// Gets one value per parameter, or None for a parameter that wasn't given
+type KeywordFn = dyn Fn(&Interpreter, Vec<Option<Object>>) -> Result<Object, LoxResult>;
+
+pub struct NativeFunction {
+    name: String,
+    arity: usize,
+    // The most arguments accepted, or None for any number
+    max_arity: Option<usize>,
+    func: Rc<NativeFn>,
+    // The parameter names of a function that takes named arguments
+    keywords: Option<(Rc<Vec<String>>, Rc<KeywordFn>)>,
+}
+
+impl NativeFunction {
+    pub fn new(
+        name: &str,
+        arity: usize,
+        func: impl Fn(&Interpreter, Vec<Object>) -> Result<Object, LoxResult> + 'static,
+    ) -> Self {
+        Self {
+            name: name.to_string(),
+            arity,
+            max_arity: Some(arity),
+            func: Rc::new(func),
+            keywords: None,
+        }
+    }
+
+    // Takes an optional argument for each of `params`, by position or by name
+    pub fn with_params(
+        name: &str,
+        params: Vec<String>,
+        func: impl Fn(&Interpreter, Vec<Option<Object>>) -> Result<Object, LoxResult> + 'static,
+    ) -> Self {
+        let func: Rc<KeywordFn> = Rc::new(func);
+        let positional = Rc::clone(&func);
+        Self {
+            keywords: Some((Rc::new(params.clone()), func)),
+            ..Self::with_optional(name, 0, params.len(), move |interpreter, args| {
+                let mut values = args.into_iter().map(Some).collect::<Vec<Option<Object>>>();
+                values.resize(params.len(), None);
+                positional(interpreter, values)
+            })
+        }
+    }
+
+    // Takes `arity` arguments and up to `optional` more; the function gets
+    // only the arguments that were given
+    pub fn with_optional(
+        name: &str,
+        arity: usize,
+        optional: usize,
+        func: impl Fn(&Interpreter, Vec<Object>) -> Result<Object, LoxResult> + 'static,
+    ) -> Self {
+        Self {
+            max_arity: Some(arity + optional),
+            ..Self::new(name, arity, func)
+        }
+    }
+
+    // Takes at least `arity` arguments
+    pub fn variadic(
+        name: &str,
+        arity: usize,
+        func: impl Fn(&Interpreter, Vec<Object>) -> Result<Object, LoxResult> + 'static,
+    ) -> Self {
+        Self {
+            max_arity: None,
+            ..Self::new(name, arity, func)
+        }
+    }
+}
+
+impl fmt::Display for NativeFunction {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f, "<NativeClock>")
+        write!(f, "<Native {}>", self.name)
     }
 }
 
-impl fmt::Debug for NativeClock {
+impl fmt::Debug for NativeFunction {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         write!(f, "{}", self)
     }
 }
 
-impl Clone for NativeClock {
-    fn clone(&self) -> Self {
-        Self {}
-    }
-}
-impl PartialEq for NativeClock {
+impl PartialEq for NativeFunction {
     fn eq(&self, other: &Self) -> bool {
-        self == other
+        Rc::ptr_eq(&self.func, &other.func)
     }
 }
 
-impl LoxCallable for NativeClock {
+impl LoxCallable for NativeFunction {
     fn call(
         &self,
-        _interpreter: &Interpreter,
-        _arguments: Vec<Object>,
+        interpreter: &Interpreter,
+        arguments: Vec<Object>,
         _klass: Option<Rc<LoxClass>>,
     ) -> Result<Object, LoxResult> {
-        match SystemTime::now().duration_since(SystemTime::UNIX_EPOCH) {
-            Ok(n) => return Ok(Object::Num(n.as_millis() as f64)),
-            Err(e) => Err(LoxResult::SystemError {
-                message: format!("Clock returned invalid duration : {:?}", e.duration()),
-            }),
-        }
+        (self.func)(interpreter, arguments)
     }
 
     fn arity(&self) -> usize {
-        0
+        self.arity
+    }
+
+    fn call_with(
+        &self,
+        interpreter: &Interpreter,
+        arguments: CallArguments,
+        klass: Option<Rc<LoxClass>>,
+    ) -> Result<Object, LoxResult> {
+        if let Some((params, func)) = &self.keywords {
+            let bound = bind_arguments(&self.name, params, params.len(), false, arguments)?;
+            return func(interpreter, bound.values);
+        }
+        if let Some((name, _)) = arguments.named.first() {
+            return Err(LoxResult::native_error(&format!(
+                "Unexpected named argument '{}'.",
+                name.as_string()
+            )));
+        }
+
+        let given = arguments.positional.len();
+        let expected = match self.max_arity {
+            Some(max) if max == self.arity => self.arity.to_string(),
+            Some(max) => format!("{} to {max}", self.arity),
+            None => format!("at least {}", self.arity),
+        };
+        if given < self.arity || self.max_arity.is_some_and(|max| given > max) {
+            return Err(LoxResult::native_error(&format!(
+                "Expected {expected} arguments but got {given}."
+            )));
+        }
+        self.call(interpreter, arguments.positional, klass)
+    }
+}
+
+pub fn define_natives(globals: &mut Environment) {
+    let natives = [
+        NativeFunction::new("clock", 0, clock),
+        NativeFunction::new("len", 1, len),
+        NativeFunction::new("chr", 1, chr),
+        NativeFunction::new("parseNumber", 1, parse_number_native),
+        NativeFunction::new("random", 0, random),
+        NativeFunction::new("randomInt", 2, random_int),
+        NativeFunction::new("shuffle", 1, shuffle),
+        NativeFunction::new("choice", 1, choice),
+        NativeFunction::new("seed", 1, seed),
+        NativeFunction::new("input", 1, input),
+        NativeFunction::new("readLine", 0, read_line_native),
+        NativeFunction::new("readFile", 1, read_file),
+        NativeFunction::new("writeFile", 2, write_file),
+        NativeFunction::new("appendFile", 2, append_file),
+        NativeFunction::new("exists", 1, exists),
+        NativeFunction::new("listDir", 1, list_dir),
+        NativeFunction::new("deleteFile", 1, delete_file),
+        NativeFunction::new("args", 0, script_args),
+        NativeFunction::new("getenv", 1, getenv),
+        NativeFunction::new("exit", 1, exit),
+        NativeFunction::new("now", 0, now),
+        NativeFunction::new("sleep", 1, sleep),
+        NativeFunction::variadic("format", 1, format),
+        NativeFunction::variadic("write", 0, write),
+    ];
+
+    for native in natives {
+        globals.make_constant(&native.name);
+        globals.define(&native.name.clone(), Object::Native(Rc::new(native)));
+    }
+}
+
+pub fn define_namespace(globals: &mut Environment, namespace: LoxNamespace) {
+    let name = namespace.name().to_string();
+    globals.make_constant(&name);
+    globals.define(&name, Object::Namespace(Rc::new(namespace)));
+}
+
+fn clock(_: &Interpreter, _: Vec<Object>) -> Result<Object, LoxResult> {
+    match SystemTime::now().duration_since(SystemTime::UNIX_EPOCH) {
+        Ok(n) => Ok(Object::Num(n.as_millis() as f64)),
+        Err(e) => Err(LoxResult::native_error(&format!(
+            "Clock returned invalid duration : {:?}",
+            e.duration()
+        ))),
+    }
+}
+
+fn len(_: &Interpreter, arguments: Vec<Object>) -> Result<Object, LoxResult> {
+    let len = match &arguments[0] {
+        Object::Str(s) => s.chars().count() as f64,
+        Object::List(list) => list.borrow().len() as f64,
+        Object::Map(map) => map.len() as f64,
+        Object::Range(range) => range.len(),
+        other => {
+            return Err(LoxResult::native_error(&format!(
+                "A value of type '{}' has no length.",
+                other.type_name()
+            )))
+        }
+    };
+    Ok(Object::Num(len))
+}
+
+// The string holding the single character with the given Unicode code point
+fn chr(_: &Interpreter, arguments: Vec<Object>) -> Result<Object, LoxResult> {
+    match &arguments[0] {
+        Object::Num(n) if n.fract() == 0.0 && *n >= 0.0 && *n <= u32::MAX as f64 => {
+            match char::from_u32(*n as u32) {
+                Some(c) => Ok(Object::Str(c.to_string())),
+                None => Err(LoxResult::native_error(&format!(
+                    "{n} is not a valid character code."
+                ))),
+            }
+        }
+        other => Err(LoxResult::native_error(&format!(
+            "chr() expects a character code, not {}.",
+            other.repr()
+        ))),
+    }
+}
+
+fn parse_number_native(_: &Interpreter, arguments: Vec<Object>) -> Result<Object, LoxResult> {
+    string_arg("parseNumber", &arguments[0]).map(parse_number)
+}
+
+// Parses decimal numbers the way Lox writes them, with an optional sign and
+// surrounding whitespace. Anything else, including "inf" and "NaN", is nil.
+pub fn parse_number(s: &str) -> Object {
+    let s = s.trim();
+    let digits = s.strip_prefix(['-', '+']).unwrap_or(s);
+    let valid = !digits.is_empty()
+        && digits.chars().all(|c| c.is_ascii_digit() || c == '.')
+        && digits.matches('.').count() <= 1
+        && !digits.starts_with('.')
+        && !digits.ends_with('.');
+    match s.parse::<f64>() {
+        Ok(n) if valid => Object::Num(n),
+        _ => Object::Nil,
+    }
+}
+
+fn list_argument<'a>(native: &str, value: &'a Object) -> Result<&'a Rc<LoxList>, LoxResult> {
+    match value {
+        Object::List(list) => Ok(list),
+        _ => Err(LoxResult::native_error(&format!(
+            "{native}() expects a list, not '{}'.",
+            value.type_name()
+        ))),
+    }
+}
+
+fn random(interpreter: &Interpreter, _: Vec<Object>) -> Result<Object, LoxResult> {
+    Ok(Object::Num(interpreter.random().next_f64()))
+}
+
+// A whole number from `lo` to `hi`, both included
+fn random_int(interpreter: &Interpreter, arguments: Vec<Object>) -> Result<Object, LoxResult> {
+    let lo = integer_arg("randomInt", &arguments[0])?;
+    let hi = integer_arg("randomInt", &arguments[1])?;
+    if lo > hi {
+        return Err(LoxResult::native_error(&format!(
+            "randomInt() bounds are the wrong way round: {lo} is more than {hi}."
+        )));
+    }
+    let offset = interpreter
+        .random()
+        .below(hi.abs_diff(lo).saturating_add(1));
+    Ok(Object::Num(lo as f64 + offset as f64))
+}
+
+// Shuffles the list in place
+fn shuffle(interpreter: &Interpreter, arguments: Vec<Object>) -> Result<Object, LoxResult> {
+    let mut list = list_argument("shuffle", &arguments[0])?.borrow_mut();
+    let mut random = interpreter.random();
+    for i in (1..list.len()).rev() {
+        let j = random.below(i as u64 + 1) as usize;
+        list.swap(i, j);
+    }
+    Ok(Object::Nil)
+}
+
+fn choice(interpreter: &Interpreter, arguments: Vec<Object>) -> Result<Object, LoxResult> {
+    let list = list_argument("choice", &arguments[0])?.borrow();
+    if list.is_empty() {
+        return Err(LoxResult::native_error("Can't choose from an empty list."));
+    }
+    let i = interpreter.random().below(list.len() as u64) as usize;
+    Ok(list[i].clone())
+}
+
+fn seed(interpreter: &Interpreter, arguments: Vec<Object>) -> Result<Object, LoxResult> {
+    let seed = integer_arg("seed", &arguments[0])?;
+    interpreter.seed_random(seed as u64);
+    Ok(Object::Nil)
+}
+
+// The command line arguments that followed the script's path
+fn script_args(interpreter: &Interpreter, _: Vec<Object>) -> Result<Object, LoxResult> {
+    let args = interpreter
+        .script_args()
+        .into_iter()
+        .map(Object::Str)
+        .collect();
+    Ok(Object::List(Rc::new(LoxList::new(args))))
+}
+
+// The value of an environment variable, or nil if it isn't set
+fn getenv(_: &Interpreter, arguments: Vec<Object>) -> Result<Object, LoxResult> {
+    let name = string_arg("getenv", &arguments[0])?;
+    Ok(std::env::var(name).map_or(Object::Nil, Object::Str))
+}
+
+fn exit(_: &Interpreter, arguments: Vec<Object>) -> Result<Object, LoxResult> {
+    let code = integer_arg("exit", &arguments[0])?;
+    let Ok(code) = i32::try_from(code) else {
+        return Err(LoxResult::native_error(&format!(
+            "{code} is not a valid exit code."
+        )));
+    };
+    let _ = io::stdout().flush();
+    std::process::exit(code)
+}
+
+// Milliseconds since the Unix epoch, with a fractional part
+fn now(_: &Interpreter, _: Vec<Object>) -> Result<Object, LoxResult> {
+    match SystemTime::now().duration_since(SystemTime::UNIX_EPOCH) {
+        Ok(n) => Ok(Object::Num(n.as_secs_f64() * 1000.0)),
+        Err(e) => Err(LoxResult::native_error(&format!(
+            "System time is before the Unix epoch by {:?}.",
+            e.duration()
+        ))),
+    }
+}
+
+fn sleep(_: &Interpreter, arguments: Vec<Object>) -> Result<Object, LoxResult> {
+    match &arguments[0] {
+        Object::Num(ms) if *ms >= 0.0 && ms.is_finite() => {
+            let duration = Duration::try_from_secs_f64(ms / 1000.0).map_err(|_| {
+                LoxResult::native_error(&format!("Can't sleep for {ms} milliseconds."))
+            })?;
+            let _ = io::stdout().flush();
+            std::thread::sleep(duration);
+            Ok(Object::Nil)
+        }
+        other => Err(LoxResult::native_error(&format!(
+            "sleep() expects a number of milliseconds, not {}.",
+            other.repr()
+        ))),
     }
 }
@@ -0,0 +1,91 @@
+use crate::error::*;
+use crate::interpreter::*;
+use crate::lox_list::*;
+use crate::native_methods::*;
+use crate::object::*;
+use std::fs;
+use std::io::{self, BufRead, Write};
+use std::rc::Rc;
+
+// Errors from the OS keep their own message, prefixed with what was being done
+fn os_error(action: &str, path: &str, error: io::Error) -> LoxResult {
+    LoxResult::native_error(&format!("Can't {action} '{path}': {error}."))
+}
+
+// The next line of standard input without its line ending, or nil at the end
+fn read_line() -> Result<Object, LoxResult> {
+    let mut line = String::new();
+    match io::stdin().lock().read_line(&mut line) {
+        Ok(0) => Ok(Object::Nil),
+        Ok(_) => {
+            let end = line.trim_end_matches(['\n', '\r']).len();
+            line.truncate(end);
+            Ok(Object::Str(line))
+        }
+        Err(e) => Err(LoxResult::native_error(&format!(
+            "Can't read from standard input: {e}."
+        ))),
+    }
+}
+
+pub fn input(_: &Interpreter, arguments: Vec<Object>) -> Result<Object, LoxResult> {
+    print!("{}", arguments[0]);
+    let _ = io::stdout().flush();
+    read_line()
+}
+
+pub fn read_line_native(_: &Interpreter, _: Vec<Object>) -> Result<Object, LoxResult> {
+    read_line()
+}
+
+pub fn read_file(_: &Interpreter, arguments: Vec<Object>) -> Result<Object, LoxResult> {
+    let path = string_arg("readFile", &arguments[0])?;
+    fs::read_to_string(path)
+        .map(Object::Str)
+        .map_err(|e| os_error("read", path, e))
+}
+
+pub fn write_file(_: &Interpreter, arguments: Vec<Object>) -> Result<Object, LoxResult> {
+    let path = string_arg("writeFile", &arguments[0])?;
+    let text = string_arg("writeFile", &arguments[1])?;
+    fs::write(path, text).map_err(|e| os_error("write", path, e))?;
+    Ok(Object::Nil)
+}
+
+pub fn append_file(_: &Interpreter, arguments: Vec<Object>) -> Result<Object, LoxResult> {
+    let path = string_arg("appendFile", &arguments[0])?;
+    let text = string_arg("appendFile", &arguments[1])?;
+    fs::OpenOptions::new()
+        .append(true)
+        .create(true)
+        .open(path)
+        .and_then(|mut file| file.write_all(text.as_bytes()))
+        .map_err(|e| os_error("append to", path, e))?;
+    Ok(Object::Nil)
+}
+
+pub fn exists(_: &Interpreter, arguments: Vec<Object>) -> Result<Object, LoxResult> {
+    let path = string_arg("exists", &arguments[0])?;
+    Ok(Object::Bool(fs::metadata(path).is_ok()))
+}
+
+// The names of the entries in a directory, sorted
+pub fn list_dir(_: &Interpreter, arguments: Vec<Object>) -> Result<Object, LoxResult> {
+    let path = string_arg("listDir", &arguments[0])?;
+    let mut names = fs::read_dir(path)
+        .and_then(|entries| {
+            entries
+                .map(|entry| entry.map(|e| e.file_name().to_string_lossy().into_owned()))
+                .collect::<io::Result<Vec<String>>>()
+        })
+        .map_err(|e| os_error("list", path, e))?;
+    names.sort();
+    let names = names.into_iter().map(Object::Str).collect();
+    Ok(Object::List(Rc::new(LoxList::new(names))))
+}
+
+pub fn delete_file(_: &Interpreter, arguments: Vec<Object>) -> Result<Object, LoxResult> {
+    let path = string_arg("deleteFile", &arguments[0])?;
+    fs::remove_file(path).map_err(|e| os_error("delete", path, e))?;
+    Ok(Object::Nil)
+}
@@ -0,0 +1,562 @@
+use crate::error::*;
+use crate::lox_list::*;
+use crate::lox_map::*;
+use crate::lox_namespace::*;
+use crate::native_functions::*;
+use crate::native_methods::*;
+use crate::object::*;
+use std::rc::Rc;
+
+pub fn json_namespace() -> LoxNamespace {
+    let mut json = LoxNamespace::new("json");
+
+    json.define(
+        "parse",
+        Object::Native(Rc::new(NativeFunction::new("json.parse", 1, |_, args| {
+            JsonParser::new(string_arg("json.parse", &args[0])?).parse()
+        }))),
+    );
+
+    // `indent` is the number of spaces to indent nested values by; without
+    // it, or with 0, everything goes on one line
+    json.define(
+        "stringify",
+        Object::Native(Rc::new(NativeFunction::with_optional(
+            "json.stringify",
+            1,
+            1,
+            |_, args| {
+                let indent = match args.get(1) {
+                    None | Some(Object::Nil) => 0,
+                    Some(Object::Num(n)) if n.fract() == 0.0 && (0.0..=16.0).contains(n) => {
+                        *n as usize
+                    }
+                    Some(other) => {
+                        return Err(LoxResult::native_error(&format!(
+                            "'json.stringify' expects an indent from 0 to 16, not {}.",
+                            other.repr()
+                        )))
+                    }
+                };
+                let mut writer = JsonWriter {
+                    indent,
+                    out: String::new(),
+                    path: Vec::new(),
+                };
+                writer.write(&args[0])?;
+                Ok(Object::Str(writer.out))
+            },
+        ))),
+    );
+
+    json
+}
+
+struct JsonParser {
+    chars: Vec<char>,
+    current: usize,
+    line: usize,
+    column: usize,
+    depth: usize,
+}
+
+impl JsonParser {
+    fn new(text: &str) -> Self {
+        Self {
+            chars: text.chars().collect(),
+            current: 0,
+            line: 1,
+            column: 1,
+            depth: 0,
+        }
+    }
+
+    fn parse(mut self) -> Result<Object, LoxResult> {
+        let value = self.value()?;
+        self.skip_whitespace();
+        if self.peek().is_some() {
+            return Err(self.error("Unexpected text after the JSON value."));
+        }
+        Ok(value)
+    }
+
+    fn error(&self, message: &str) -> LoxResult {
+        LoxResult::native_error(&format!(
+            "Invalid JSON at line {}, column {}: {message}",
+            self.line, self.column
+        ))
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.current).copied()
+    }
+
+    fn advance(&mut self) -> Option<char> {
+        let c = self.peek()?;
+        self.current += 1;
+        if c == '\n' {
+            self.line += 1;
+            self.column = 1;
+        } else {
+            self.column += 1;
+        }
+        Some(c)
+    }
+
+    fn expect(&mut self, expected: char) -> Result<(), LoxResult> {
+        match self.peek() {
+            Some(c) if c == expected => {
+                self.advance();
+                Ok(())
+            }
+            Some(c) => Err(self.error(&format!("Expected '{expected}' but found '{c}'."))),
+            None => Err(self.error(&format!("Expected '{expected}' but the text ended."))),
+        }
+    }
+
+    fn skip_whitespace(&mut self) {
+        while matches!(self.peek(), Some(' ' | '\t' | '\n' | '\r')) {
+            self.advance();
+        }
+    }
+
+    fn value(&mut self) -> Result<Object, LoxResult> {
+        self.skip_whitespace();
+        match self.peek() {
+            Some('{' | '[') => self.nested(),
+            Some('"') => self.string().map(Object::Str),
+            Some('-' | '0'..='9') => self.number(),
+            Some('t') => self.keyword("true", Object::Bool(true)),
+            Some('f') => self.keyword("false", Object::Bool(false)),
+            Some('n') => self.keyword("null", Object::Nil),
+            Some(c) => Err(self.error(&format!("Unexpected character '{c}'."))),
+            None => Err(self.error("Expected a value but the text ended.")),
+        }
+    }
+
+    fn keyword(&mut self, word: &str, value: Object) -> Result<Object, LoxResult> {
+        for expected in word.chars() {
+            if self.peek() != Some(expected) {
+                return Err(self.error(&format!("Expected '{word}'.")));
+            }
+            self.advance();
+        }
+        Ok(value)
+    }
+
+    // Objects and arrays, which can't be nested deeper than the writer allows
+    fn nested(&mut self) -> Result<Object, LoxResult> {
+        if self.depth == MAX_DEPTH {
+            return Err(self.error("Nesting too deep."));
+        }
+        self.depth += 1;
+        let value = if self.peek() == Some('{') {
+            self.object()
+        } else {
+            self.array()
+        };
+        self.depth -= 1;
+        value
+    }
+
+    fn object(&mut self) -> Result<Object, LoxResult> {
+        self.expect('{')?;
+        let map = LoxMap::new();
+        self.skip_whitespace();
+        if self.peek() == Some('}') {
+            self.advance();
+            return Ok(Object::Map(Rc::new(map)));
+        }
+
+        loop {
+            self.skip_whitespace();
+            if self.peek() != Some('"') {
+                return Err(self.error("Expected a string key."));
+            }
+            let key = self.string()?;
+            self.skip_whitespace();
+            self.expect(':')?;
+            let value = self.value()?;
+            map.insert(Object::Str(key), value);
+
+            self.skip_whitespace();
+            match self.advance() {
+                Some(',') => continue,
+                Some('}') => break,
+                _ => return Err(self.error("Expected ',' or '}' after an object member.")),
+            }
+        }
+        Ok(Object::Map(Rc::new(map)))
+    }
+
+    fn array(&mut self) -> Result<Object, LoxResult> {
+        self.expect('[')?;
+        let mut elements = Vec::new();
+        self.skip_whitespace();
+        if self.peek() == Some(']') {
+            self.advance();
+            return Ok(Object::List(Rc::new(LoxList::new(elements))));
+        }
+
+        loop {
+            elements.push(self.value()?);
+            self.skip_whitespace();
+            match self.advance() {
+                Some(',') => continue,
+                Some(']') => break,
+                _ => return Err(self.error("Expected ',' or ']' after an array element.")),
+            }
+        }
+        Ok(Object::List(Rc::new(LoxList::new(elements))))
+    }
+
+    fn string(&mut self) -> Result<String, LoxResult> {
+        self.expect('"')?;
+        let mut s = String::new();
+        loop {
+            match self.advance() {
+                Some('"') => return Ok(s),
+                Some('\\') => s.push(self.escape()?),
+                Some(c) if (c as u32) < 0x20 => {
+                    return Err(self.error("Control characters must be escaped in strings."))
+                }
+                Some(c) => s.push(c),
+                None => return Err(self.error("Unterminated string.")),
+            }
+        }
+    }
+
+    fn escape(&mut self) -> Result<char, LoxResult> {
+        Ok(match self.advance() {
+            Some('"') => '"',
+            Some('\\') => '\\',
+            Some('/') => '/',
+            Some('b') => '\u{8}',
+            Some('f') => '\u{c}',
+            Some('n') => '\n',
+            Some('r') => '\r',
+            Some('t') => '\t',
+            Some('u') => {
+                let high = self.hex4()?;
+                if !(0xD800..0xDC00).contains(&high) {
+                    return char::from_u32(high)
+                        .ok_or_else(|| self.error("Invalid unicode escape."));
+                }
+                // Characters outside the basic plane are written as a
+                // surrogate pair, like `\uD83D\uDE00`
+                if self.advance() != Some('\\') || self.advance() != Some('u') {
+                    return Err(self.error("Expected a low surrogate after a high surrogate."));
+                }
+                let low = self.hex4()?;
+                if !(0xDC00..0xE000).contains(&low) {
+                    return Err(self.error("Invalid low surrogate."));
+                }
+                let code = 0x10000 + ((high - 0xD800) << 10) + (low - 0xDC00);
+                char::from_u32(code).ok_or_else(|| self.error("Invalid unicode escape."))?
+            }
+            Some(c) => return Err(self.error(&format!("Invalid escape '\\{c}'."))),
+            None => return Err(self.error("Unterminated string.")),
+        })
+    }
+
+    fn hex4(&mut self) -> Result<u32, LoxResult> {
+        let mut code = 0;
+        for _ in 0..4 {
+            let digit = self
+                .peek()
+                .and_then(|c| c.to_digit(16))
+                .ok_or_else(|| self.error("Expected four hex digits after '\\u'."))?;
+            self.advance();
+            code = code * 16 + digit;
+        }
+        Ok(code)
+    }
+
+    fn number(&mut self) -> Result<Object, LoxResult> {
+        let start = self.current;
+        if self.peek() == Some('-') {
+            self.advance();
+        }
+        match self.peek() {
+            Some('0') => {
+                self.advance();
+            }
+            Some('1'..='9') => self.digits(),
+            _ => return Err(self.error("Expected a digit.")),
+        }
+        if self.peek() == Some('.') {
+            self.advance();
+            if !matches!(self.peek(), Some('0'..='9')) {
+                return Err(self.error("Expected a digit after the decimal point."));
+            }
+            self.digits();
+        }
+        if matches!(self.peek(), Some('e' | 'E')) {
+            self.advance();
+            if matches!(self.peek(), Some('+' | '-')) {
+                self.advance();
+            }
+            if !matches!(self.peek(), Some('0'..='9')) {
+                return Err(self.error("Expected a digit in the exponent."));
+            }
+            self.digits();
+        }
+
+        let text = self.chars[start..self.current].iter().collect::<String>();
+        text.parse::<f64>()
+            .map(Object::Num)
+            .map_err(|_| self.error(&format!("Invalid number '{text}'.")))
+    }
+
+    fn digits(&mut self) {
+        while matches!(self.peek(), Some('0'..='9')) {
+            self.advance();
+        }
+    }
+}
+
+// Reading and writing recurse once per level of nesting, so both stop here
+const MAX_DEPTH: usize = 512;
+
+struct JsonWriter {
+    indent: usize,
+    out: String,
+    // The lists, maps and instances being written, outermost first
+    path: Vec<*const ()>,
+}
+
+impl JsonWriter {
+    fn write(&mut self, value: &Object) -> Result<(), LoxResult> {
+        match value {
+            Object::Nil => self.out.push_str("null"),
+            Object::Bool(b) => self.out.push_str(&b.to_string()),
+            Object::Num(n) if n.is_finite() => self.out.push_str(&n.to_string()),
+            Object::Num(n) => {
+                return Err(LoxResult::native_error(&format!(
+                    "Can't convert {n} to JSON."
+                )))
+            }
+            Object::Str(s) => self.string(s),
+            Object::EnumValue(v) if v.payload().is_empty() => self.string(v.name()),
+            Object::List(list) => {
+                let elements = list.borrow().clone();
+                self.nested(
+                    Rc::as_ptr(list) as *const (),
+                    '[',
+                    ']',
+                    &elements,
+                    |writer, element| writer.write(element),
+                )?;
+            }
+            Object::Map(map) => {
+                let mut members = Vec::new();
+                for key in map.keys() {
+                    let Object::Str(name) = &key else {
+                        return Err(LoxResult::native_error(&format!(
+                            "Can't convert a map with key {} to JSON; keys must be strings.",
+                            key.repr()
+                        )));
+                    };
+                    members.push((name.clone(), map.get(&key).unwrap_or(Object::Nil)));
+                }
+                self.members(Rc::as_ptr(map) as *const (), &members)?;
+            }
+            Object::Instance(instance) => {
+                self.members(Rc::as_ptr(instance) as *const (), &instance.public_fields())?
+            }
+            other => {
+                return Err(LoxResult::native_error(&format!(
+                    "Can't convert a value of type '{}' to JSON.",
+                    other.type_name()
+                )))
+            }
+        }
+        Ok(())
+    }
+
+    fn members(
+        &mut self,
+        container: *const (),
+        members: &[(String, Object)],
+    ) -> Result<(), LoxResult> {
+        let separator = if self.indent > 0 { ": " } else { ":" };
+        self.nested(container, '{', '}', members, |writer, (name, value)| {
+            writer.string(name);
+            writer.out.push_str(separator);
+            writer.write(value)
+        })
+    }
+
+    fn nested<T>(
+        &mut self,
+        container: *const (),
+        open: char,
+        close: char,
+        items: &[T],
+        mut write_item: impl FnMut(&mut Self, &T) -> Result<(), LoxResult>,
+    ) -> Result<(), LoxResult> {
+        if self.path.contains(&container) {
+            return Err(LoxResult::native_error(
+                "Can't convert a value that contains itself to JSON.",
+            ));
+        }
+        if self.path.len() == MAX_DEPTH {
+            return Err(LoxResult::native_error(&format!(
+                "Can't convert a value nested more than {MAX_DEPTH} deep to JSON."
+            )));
+        }
+
+        self.out.push(open);
+        self.path.push(container);
+        for (i, item) in items.iter().enumerate() {
+            if i > 0 {
+                self.out.push(',');
+            }
+            self.newline();
+            write_item(self, item)?;
+        }
+        self.path.pop();
+        if !items.is_empty() {
+            self.newline();
+        }
+        self.out.push(close);
+        Ok(())
+    }
+
+    fn newline(&mut self) {
+        if self.indent > 0 {
+            self.out.push('\n');
+            self.out
+                .push_str(&" ".repeat(self.indent * self.path.len()));
+        }
+    }
+
+    fn string(&mut self, s: &str) {
+        self.out.push('"');
+        for c in s.chars() {
+            match c {
+                '"' => self.out.push_str("\\\""),
+                '\\' => self.out.push_str("\\\\"),
+                '\n' => self.out.push_str("\\n"),
+                '\r' => self.out.push_str("\\r"),
+                '\t' => self.out.push_str("\\t"),
+                c if (c as u32) < 0x20 => self.out.push_str(&format!("\\u{:04x}", c as u32)),
+                c => self.out.push(c),
+            }
+        }
+        self.out.push('"');
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse_string(json: &str) -> Result<String, String> {
+        match JsonParser::new(json).parse() {
+            Ok(Object::Str(s)) => Ok(s),
+            Ok(other) => panic!("{json} parsed as {}", other.repr()),
+            Err(LoxResult::SystemError { message }) => Err(message),
+            Err(_) => panic!("{json} failed without a message"),
+        }
+    }
+
+    fn stringify(s: &str) -> String {
+        let mut writer = JsonWriter {
+            indent: 0,
+            out: String::new(),
+            path: Vec::new(),
+        };
+        writer.string(s);
+        writer.out
+    }
+
+    #[test]
+    fn escapes_are_decoded() {
+        assert_eq!(
+            parse_string(r#""\"\\\/\b\f\n\r\t""#).unwrap(),
+            "\"\\/\u{8}\u{c}\n\r\t"
+        );
+        assert_eq!(parse_string(r#""\u0041\u00e9\u20AC""#).unwrap(), "Aé€");
+        assert_eq!(parse_string(r#""\u0000""#).unwrap(), "\0");
+    }
+
+    #[test]
+    fn surrogate_pairs_make_one_character() {
+        assert_eq!(parse_string(r#""\uD83D\uDE00""#).unwrap(), "😀");
+        assert_eq!(parse_string(r#""\uDBFF\uDFFF""#).unwrap(), "\u{10FFFF}");
+        assert_eq!(parse_string(r#""a\ud834\udd1eb""#).unwrap(), "a𝄞b");
+    }
+
+    #[test]
+    fn broken_escapes_are_rejected() {
+        let error = |json: &str| parse_string(json).unwrap_err();
+        assert!(error(r#""\uD83D""#).ends_with("Expected a low surrogate after a high surrogate."));
+        assert!(error(r#""\uD83Dx""#).ends_with("Expected a low surrogate after a high surrogate."));
+        assert!(error(r#""\uD83D\u0041""#).ends_with("Invalid low surrogate."));
+        assert!(error(r#""\uDE00""#).ends_with("Invalid unicode escape."));
+        assert!(error(r#""\u12G4""#).ends_with("Expected four hex digits after '\\u'."));
+        assert!(error(r#""\x""#).ends_with("Invalid escape '\\x'."));
+        assert!(error("\"a\nb\"").ends_with("Control characters must be escaped in strings."));
+    }
+
+    #[test]
+    fn strings_round_trip() {
+        let texts = [
+            "plain",
+            "quote \" and \\ slash",
+            "tab\tline\nfeed\r",
+            "\u{1}\u{1f}",
+            "😀é",
+        ];
+        for text in texts {
+            let json = stringify(text);
+            assert_eq!(parse_string(&json).unwrap(), text, "{json}");
+        }
+        assert_eq!(stringify("\u{1}\"\n"), r#""\u0001\"\n""#);
+    }
+
+    fn write(value: &Object) -> Result<String, String> {
+        let mut writer = JsonWriter {
+            indent: 0,
+            out: String::new(),
+            path: Vec::new(),
+        };
+        match writer.write(value) {
+            Ok(()) => Ok(writer.out),
+            Err(LoxResult::SystemError { message }) => Err(message),
+            Err(_) => panic!("writing failed without a message"),
+        }
+    }
+
+    #[test]
+    fn only_cycles_count_as_containing_themselves() {
+        let shared = Object::List(Rc::new(LoxList::new(vec![Object::Num(1.0)])));
+        let twice = Object::List(Rc::new(LoxList::new(vec![shared.clone(), shared])));
+        assert_eq!(write(&twice).unwrap(), "[[1],[1]]");
+
+        let cycle = Rc::new(LoxList::default());
+        cycle.borrow_mut().push(Object::List(Rc::clone(&cycle)));
+        assert_eq!(
+            write(&Object::List(Rc::clone(&cycle))).unwrap_err(),
+            "Can't convert a value that contains itself to JSON."
+        );
+        cycle.take();
+
+        let mut deep = Object::Nil;
+        for _ in 0..=MAX_DEPTH {
+            deep = Object::List(Rc::new(LoxList::new(vec![deep])));
+        }
+        assert_eq!(
+            write(&deep).unwrap_err(),
+            format!("Can't convert a value nested more than {MAX_DEPTH} deep to JSON.")
+        );
+    }
+
+    #[test]
+    fn nesting_is_limited() {
+        let nested = |depth: usize| "[".repeat(depth) + &"]".repeat(depth);
+        assert!(JsonParser::new(&nested(MAX_DEPTH)).parse().is_ok());
+        assert!(JsonParser::new(&nested(MAX_DEPTH + 1)).parse().is_err());
+    }
+}
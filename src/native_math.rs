@@ -0,0 +1,112 @@
+use crate::error::*;
+use crate::lox_namespace::*;
+use crate::native_functions::*;
+use crate::object::*;
+use std::f64::consts;
+use std::rc::Rc;
+
+type Unary = fn(f64) -> f64;
+type Binary = fn(f64, f64) -> f64;
+
+pub fn math_namespace() -> LoxNamespace {
+    let mut math = LoxNamespace::new("math");
+
+    math.define("PI", Object::Num(consts::PI));
+    math.define("E", Object::Num(consts::E));
+    math.define("INFINITY", Object::Num(f64::INFINITY));
+
+    let unary: [(&str, Unary); 17] = [
+        ("sqrt", f64::sqrt),
+        ("abs", f64::abs),
+        ("floor", f64::floor),
+        ("ceil", f64::ceil),
+        ("round", f64::round),
+        ("trunc", f64::trunc),
+        ("sign", sign),
+        ("sin", f64::sin),
+        ("cos", f64::cos),
+        ("tan", f64::tan),
+        ("asin", f64::asin),
+        ("acos", f64::acos),
+        ("atan", f64::atan),
+        ("log", f64::ln),
+        ("log10", f64::log10),
+        ("log2", f64::log2),
+        ("exp", f64::exp),
+    ];
+    for (name, f) in unary {
+        define(&mut math, name, 1, move |args| Ok(Object::Num(f(args[0]))));
+    }
+
+    let binary: [(&str, Binary); 4] = [
+        ("pow", f64::powf),
+        ("atan2", f64::atan2),
+        ("min", f64::min),
+        ("max", f64::max),
+    ];
+    for (name, f) in binary {
+        define(&mut math, name, 2, move |args| {
+            Ok(Object::Num(f(args[0], args[1])))
+        });
+    }
+
+    define(&mut math, "isNaN", 1, |args| {
+        Ok(Object::Bool(args[0].is_nan()))
+    });
+    define(&mut math, "isFinite", 1, |args| {
+        Ok(Object::Bool(args[0].is_finite()))
+    });
+    // Integer division rounds towards negative infinity, and `mod` agrees
+    // with it so that `div(a, b) * b + mod(a, b) == a`
+    define(&mut math, "div", 2, |args| {
+        Ok(Object::Num(whole_divisor("div", args)?.0))
+    });
+    define(&mut math, "mod", 2, |args| {
+        Ok(Object::Num(whole_divisor("mod", args)?.1))
+    });
+
+    math
+}
+
+// Defines a function of `arity` numbers
+fn define(
+    namespace: &mut LoxNamespace,
+    name: &str,
+    arity: usize,
+    func: impl Fn(&[f64]) -> Result<Object, LoxResult> + 'static,
+) {
+    let qualified = format!("{}.{name}", namespace.name());
+    let native = NativeFunction::new(&qualified.clone(), arity, move |_, args| {
+        let numbers = args
+            .iter()
+            .map(|arg| match arg {
+                Object::Num(n) => Ok(*n),
+                _ => Err(LoxResult::native_error(&format!(
+                    "'{qualified}' expects numbers, not '{}'.",
+                    arg.type_name()
+                ))),
+            })
+            .collect::<Result<Vec<f64>, LoxResult>>()?;
+        func(&numbers)
+    });
+    namespace.define(name, Object::Native(Rc::new(native)));
+}
+
+fn sign(n: f64) -> f64 {
+    if n == 0.0 || n.is_nan() {
+        n
+    } else {
+        n.signum()
+    }
+}
+
+fn whole_divisor(name: &str, args: &[f64]) -> Result<(f64, f64), LoxResult> {
+    let (a, b) = (args[0], args[1]);
+    if b == 0.0 {
+        return Err(LoxResult::native_error(&format!(
+            "'math.{name}' can't divide by zero."
+        )));
+    }
+    let quotient = (a / b).floor();
+    Ok((quotient, a - quotient * b))
+}
@@ -0,0 +1,439 @@
+use crate::error::*;
+use crate::lox_enum::*;
+use crate::lox_generator::*;
+use crate::lox_instance::*;
+use crate::lox_list::*;
+use crate::lox_map::*;
+use crate::lox_range::*;
+use crate::native_functions::*;
+use crate::object::*;
+use crate::token::*;
+use std::rc::Rc;
+
+// The longest string `repeat` will build, well short of exhausting memory
+const MAX_STRING_BYTES: usize = 1 << 30;
+
+// The most values `toList` will take from a range
+const MAX_RANGE_LIST: f64 = (1 << 27) as f64;
+
+// Converts a Lox number into a position in a sequence of length `len`
+pub fn as_index(index: &Object, len: usize) -> Result<usize, String> {
+    match index {
+        Object::Num(n) if n.fract() == 0.0 && *n >= 0.0 && (*n as usize) < len => Ok(*n as usize),
+        Object::Num(n) if n.fract() == 0.0 => Err(format!("Index {n} is out of range.")),
+        Object::Num(n) => Err(format!("Index {n} is not an integer.")),
+        _ => Err(format!(
+            "Index must be a number, not '{}'.",
+            index.type_name()
+        )),
+    }
+}
+
+// The positions a range picks out of a sequence of length `len`, for slicing
+pub fn slice_positions(range: &LoxRange, len: usize) -> Result<Vec<usize>, String> {
+    range
+        .values()
+        .map(|value| as_index(&Object::Num(value), len))
+        .collect()
+}
+
+pub(crate) fn string_arg<'a>(method: &str, arg: &'a Object) -> Result<&'a str, LoxResult> {
+    match arg {
+        Object::Str(s) => Ok(s),
+        _ => Err(LoxResult::native_error(&format!(
+            "'{method}' expects a string, not '{}'.",
+            arg.type_name()
+        ))),
+    }
+}
+
+pub(crate) fn number_arg(method: &str, arg: &Object) -> Result<f64, LoxResult> {
+    match arg {
+        Object::Num(n) => Ok(*n),
+        _ => Err(LoxResult::native_error(&format!(
+            "'{method}' expects a number, not '{}'.",
+            arg.type_name()
+        ))),
+    }
+}
+
+pub(crate) fn integer_arg(method: &str, arg: &Object) -> Result<i64, LoxResult> {
+    match number_arg(method, arg)? {
+        n if n.fract() == 0.0 => Ok(n as i64),
+        n => Err(LoxResult::native_error(&format!(
+            "'{method}' expects a whole number, not {n}."
+        ))),
+    }
+}
+
+// Resolves a possibly negative position counted from the end, clamped to the
+// bounds of a sequence of length `len`
+fn clamp_position(position: i64, len: usize) -> usize {
+    if position < 0 {
+        len.saturating_sub(position.unsigned_abs() as usize)
+    } else {
+        (position as usize).min(len)
+    }
+}
+
+pub(crate) fn undefined(name: &Token, receiver: &str) -> Result<Object, LoxResult> {
+    Err(LoxResult::runtime_error(
+        name,
+        &format!("Undefined property '{}' on {receiver}.", name.as_string()),
+    ))
+}
+
+pub fn list_method(list: &Rc<LoxList>, name: &Token) -> Result<Object, LoxResult> {
+    let list = Rc::clone(list);
+    let method = name.as_string();
+    let native = match method.as_str() {
+        "len" => NativeFunction::new("len", 0, move |_, _| {
+            Ok(Object::Num(list.borrow().len() as f64))
+        }),
+        "isEmpty" => NativeFunction::new("isEmpty", 0, move |_, _| {
+            Ok(Object::Bool(list.borrow().is_empty()))
+        }),
+        "push" => NativeFunction::new("push", 1, move |_, mut args| {
+            list.borrow_mut().push(args.remove(0));
+            Ok(Object::Nil)
+        }),
+        "pop" => NativeFunction::new("pop", 0, move |_, _| {
+            list.borrow_mut()
+                .pop()
+                .ok_or_else(|| LoxResult::native_error("Can't pop from an empty list."))
+        }),
+        "insert" => NativeFunction::new("insert", 2, move |_, mut args| {
+            let len = list.borrow().len();
+            let position = as_index(&args[0], len + 1).map_err(|e| LoxResult::native_error(&e))?;
+            list.borrow_mut().insert(position, args.remove(1));
+            Ok(Object::Nil)
+        }),
+        "remove" => NativeFunction::new("remove", 1, move |_, args| {
+            let len = list.borrow().len();
+            let position = as_index(&args[0], len).map_err(|e| LoxResult::native_error(&e))?;
+            Ok(list.borrow_mut().remove(position))
+        }),
+        "contains" => NativeFunction::new("contains", 1, move |_, args| {
+            Ok(Object::Bool(list.borrow().contains(&args[0])))
+        }),
+        "join" => NativeFunction::new("join", 1, move |_, args| {
+            let separator = string_arg("join", &args[0])?;
+            let parts = list
+                .borrow()
+                .iter()
+                .map(|e| e.to_string())
+                .collect::<Vec<String>>();
+            Ok(Object::Str(parts.join(separator)))
+        }),
+        _ => return undefined(name, "list"),
+    };
+    Ok(Object::Native(Rc::new(native)))
+}
+
+pub fn string_method(s: &str, name: &Token) -> Result<Object, LoxResult> {
+    let method = name.as_string();
+    // A property rather than a method, so `"abc".length` is 3
+    if method == "length" {
+        return Ok(Object::Num(s.chars().count() as f64));
+    }
+    let s = s.to_string();
+    let native = match method.as_str() {
+        "len" => NativeFunction::new("len", 0, move |_, _| {
+            Ok(Object::Num(s.chars().count() as f64))
+        }),
+        "isEmpty" => NativeFunction::new("isEmpty", 0, move |_, _| Ok(Object::Bool(s.is_empty()))),
+        "upper" => NativeFunction::new("upper", 0, move |_, _| Ok(Object::Str(s.to_uppercase()))),
+        "lower" => NativeFunction::new("lower", 0, move |_, _| Ok(Object::Str(s.to_lowercase()))),
+        "contains" => NativeFunction::new("contains", 1, move |_, args| {
+            Ok(Object::Bool(s.contains(string_arg("contains", &args[0])?)))
+        }),
+        "startsWith" => NativeFunction::new("startsWith", 1, move |_, args| {
+            Ok(Object::Bool(
+                s.starts_with(string_arg("startsWith", &args[0])?),
+            ))
+        }),
+        "endsWith" => NativeFunction::new("endsWith", 1, move |_, args| {
+            Ok(Object::Bool(s.ends_with(string_arg("endsWith", &args[0])?)))
+        }),
+        // Positions count characters, not bytes
+        "indexOf" => NativeFunction::new("indexOf", 1, move |_, args| {
+            let needle = string_arg("indexOf", &args[0])?;
+            Ok(match s.find(needle) {
+                Some(byte) => Object::Num(s[..byte].chars().count() as f64),
+                None => Object::Num(-1.0),
+            })
+        }),
+        "lastIndexOf" => NativeFunction::new("lastIndexOf", 1, move |_, args| {
+            let needle = string_arg("lastIndexOf", &args[0])?;
+            Ok(match s.rfind(needle) {
+                Some(byte) => Object::Num(s[..byte].chars().count() as f64),
+                None => Object::Num(-1.0),
+            })
+        }),
+        "substring" => NativeFunction::new("substring", 2, move |_, args| {
+            let len = s.chars().count();
+            let start = integer_arg("substring", &args[0])?;
+            let end = integer_arg("substring", &args[1])?;
+            if start < 0 || end < start || end as usize > len {
+                return Err(LoxResult::native_error(&format!(
+                    "Substring {start}..{end} is out of range for a string of length {len}."
+                )));
+            }
+            Ok(Object::Str(
+                s.chars()
+                    .skip(start as usize)
+                    .take((end - start) as usize)
+                    .collect(),
+            ))
+        }),
+        // Like substring, but negative positions count from the end and
+        // positions past either end are clamped
+        "slice" => NativeFunction::new("slice", 2, move |_, args| {
+            let len = s.chars().count();
+            let start = clamp_position(integer_arg("slice", &args[0])?, len);
+            let end = clamp_position(integer_arg("slice", &args[1])?, len);
+            Ok(Object::Str(
+                s.chars()
+                    .skip(start)
+                    .take(end.saturating_sub(start))
+                    .collect(),
+            ))
+        }),
+        "replace" => NativeFunction::new("replace", 2, move |_, args| {
+            let from = string_arg("replace", &args[0])?;
+            let to = string_arg("replace", &args[1])?;
+            if from.is_empty() {
+                return Err(LoxResult::native_error(
+                    "'replace' can't replace an empty string.",
+                ));
+            }
+            Ok(Object::Str(s.replace(from, to)))
+        }),
+        "trim" => NativeFunction::new("trim", 0, move |_, _| Ok(Object::Str(s.trim().to_string()))),
+        "trimStart" => NativeFunction::new("trimStart", 0, move |_, _| {
+            Ok(Object::Str(s.trim_start().to_string()))
+        }),
+        "trimEnd" => NativeFunction::new("trimEnd", 0, move |_, _| {
+            Ok(Object::Str(s.trim_end().to_string()))
+        }),
+        "repeat" => NativeFunction::new("repeat", 1, move |_, args| {
+            let count = integer_arg("repeat", &args[0])?;
+            let Ok(count) = usize::try_from(count) else {
+                return Err(LoxResult::native_error(&format!(
+                    "Can't repeat a string {count} times."
+                )));
+            };
+            match s.len().checked_mul(count) {
+                Some(bytes) if bytes <= MAX_STRING_BYTES => Ok(Object::Str(s.repeat(count))),
+                _ => Err(LoxResult::native_error(&format!(
+                    "Can't repeat a string {count} times; the result would be longer than {MAX_STRING_BYTES} bytes."
+                ))),
+            }
+        }),
+        "chars" => NativeFunction::new("chars", 0, move |_, _| {
+            let chars = s.chars().map(|c| Object::Str(c.to_string())).collect();
+            Ok(Object::List(Rc::new(LoxList::new(chars))))
+        }),
+        "codeAt" => NativeFunction::new("codeAt", 1, move |_, args| {
+            let position =
+                as_index(&args[0], s.chars().count()).map_err(|e| LoxResult::native_error(&e))?;
+            Ok(Object::Num(s.chars().nth(position).unwrap() as u32 as f64))
+        }),
+        "toNumber" => NativeFunction::new("toNumber", 0, move |_, _| Ok(parse_number(&s))),
+        "split" => NativeFunction::new("split", 1, move |_, args| {
+            let separator = string_arg("split", &args[0])?;
+            let parts = if separator.is_empty() {
+                s.chars().map(|c| Object::Str(c.to_string())).collect()
+            } else {
+                s.split(separator)
+                    .map(|part| Object::Str(part.to_string()))
+                    .collect()
+            };
+            Ok(Object::List(Rc::new(LoxList::new(parts))))
+        }),
+        "toString" => NativeFunction::new("toString", 0, move |_, _| Ok(Object::Str(s.clone()))),
+        _ => return undefined(name, "string"),
+    };
+    Ok(Object::Native(Rc::new(native)))
+}
+
+pub fn number_method(n: f64, name: &Token) -> Result<Object, LoxResult> {
+    let method = name.as_string();
+    let native = match method.as_str() {
+        "floor" => NativeFunction::new("floor", 0, move |_, _| Ok(Object::Num(n.floor()))),
+        "ceil" => NativeFunction::new("ceil", 0, move |_, _| Ok(Object::Num(n.ceil()))),
+        "round" => NativeFunction::new("round", 0, move |_, _| Ok(Object::Num(n.round()))),
+        "abs" => NativeFunction::new("abs", 0, move |_, _| Ok(Object::Num(n.abs()))),
+        "isInteger" => NativeFunction::new("isInteger", 0, move |_, _| {
+            Ok(Object::Bool(n.fract() == 0.0))
+        }),
+        "toFixed" => NativeFunction::new("toFixed", 1, move |_, args| {
+            let digits = number_arg("toFixed", &args[0])?;
+            if digits.fract() != 0.0 || !(0.0..=100.0).contains(&digits) {
+                return Err(LoxResult::native_error(
+                    "'toFixed' expects a whole number of digits from 0 to 100.",
+                ));
+            }
+            Ok(Object::Str(format!("{n:.*}", digits as usize)))
+        }),
+        "toString" => {
+            NativeFunction::new("toString", 0, move |_, _| Ok(Object::Str(n.to_string())))
+        }
+        _ => return undefined(name, "number"),
+    };
+    Ok(Object::Native(Rc::new(native)))
+}
+
+pub fn bool_method(b: bool, name: &Token) -> Result<Object, LoxResult> {
+    let method = name.as_string();
+    let native = match method.as_str() {
+        "toString" => {
+            NativeFunction::new("toString", 0, move |_, _| Ok(Object::Str(b.to_string())))
+        }
+        _ => return undefined(name, "boolean"),
+    };
+    Ok(Object::Native(Rc::new(native)))
+}
+
+pub fn map_method(map: &Rc<LoxMap>, name: &Token) -> Result<Object, LoxResult> {
+    let map = Rc::clone(map);
+    let method = name.as_string();
+    let native = match method.as_str() {
+        "len" => NativeFunction::new("len", 0, move |_, _| Ok(Object::Num(map.len() as f64))),
+        "isEmpty" => {
+            NativeFunction::new("isEmpty", 0, move |_, _| Ok(Object::Bool(map.is_empty())))
+        }
+        "keys" => NativeFunction::new("keys", 0, move |_, _| {
+            Ok(Object::List(Rc::new(LoxList::new(map.keys()))))
+        }),
+        "values" => NativeFunction::new("values", 0, move |_, _| {
+            Ok(Object::List(Rc::new(LoxList::new(map.values()))))
+        }),
+        "has" => NativeFunction::new("has", 1, move |_, args| {
+            Ok(Object::Bool(map.contains(&args[0])))
+        }),
+        "remove" => NativeFunction::new("remove", 1, move |_, args| {
+            Ok(map.remove(&args[0]).unwrap_or(Object::Nil))
+        }),
+        _ => return undefined(name, "map"),
+    };
+    Ok(Object::Native(Rc::new(native)))
+}
+
+pub fn range_method(range: &Rc<LoxRange>, name: &Token) -> Result<Object, LoxResult> {
+    let range = Rc::clone(range);
+    let method = name.as_string();
+    let native = match method.as_str() {
+        "len" => NativeFunction::new("len", 0, move |_, _| Ok(Object::Num(range.len()))),
+        "isEmpty" => NativeFunction::new("isEmpty", 0, move |_, _| {
+            Ok(Object::Bool(range.len() == 0.0))
+        }),
+        "contains" => NativeFunction::new("contains", 1, move |_, args| {
+            Ok(Object::Bool(match args[0] {
+                Object::Num(n) => range.contains(n),
+                _ => false,
+            }))
+        }),
+        "toList" => NativeFunction::new("toList", 0, move |_, _| {
+            let len = range.len();
+            if len.is_infinite() {
+                return Err(LoxResult::native_error(&format!(
+                    "Can't make a list of the unbounded range {range}."
+                )));
+            }
+            if len > MAX_RANGE_LIST {
+                return Err(LoxResult::native_error(&format!(
+                    "Can't make a list of the range {range}; it has more than {MAX_RANGE_LIST} values."
+                )));
+            }
+            let values = range.values().map(Object::Num).collect();
+            Ok(Object::List(Rc::new(LoxList::new(values))))
+        }),
+        _ => return undefined(name, "range"),
+    };
+    Ok(Object::Native(Rc::new(native)))
+}
+
+pub fn generator_method(generator: &Rc<LoxGenerator>, name: &Token) -> Result<Object, LoxResult> {
+    let generator = Rc::clone(generator);
+    let method = name.as_string();
+    let native = match method.as_str() {
+        "hasNext" => NativeFunction::new("hasNext", 0, move |interpreter, _| {
+            Ok(Object::Bool(generator.has_next(interpreter)?))
+        }),
+        "next" => NativeFunction::new("next", 0, move |interpreter, _| {
+            generator
+                .next(interpreter)?
+                .ok_or_else(|| LoxResult::native_error(&format!("{generator} has no more values.")))
+        }),
+        "close" => NativeFunction::new("close", 0, move |_, _| {
+            generator.close();
+            Ok(Object::Nil)
+        }),
+        _ => return undefined(name, "generator"),
+    };
+    Ok(Object::Native(Rc::new(native)))
+}
+
+pub fn enum_method(lox_enum: &Rc<LoxEnum>, name: &Token) -> Result<Object, LoxResult> {
+    if let Some(variant) = lox_enum.variant(&name.as_string()) {
+        return Ok(variant);
+    }
+
+    let lox_enum = Rc::clone(lox_enum);
+    let method = name.as_string();
+    let native = match method.as_str() {
+        "values" => NativeFunction::new("values", 0, move |_, _| {
+            Ok(Object::List(Rc::new(LoxList::new(lox_enum.values()))))
+        }),
+        "fromName" => NativeFunction::new("fromName", 1, move |_, args| {
+            Ok(match &args[0] {
+                Object::Str(name) => match lox_enum.variant(name) {
+                    Some(value @ Object::EnumValue(_)) => value,
+                    _ => Object::Nil,
+                },
+                _ => Object::Nil,
+            })
+        }),
+        "fromOrdinal" => NativeFunction::new("fromOrdinal", 1, move |_, args| {
+            Ok(match &args[0] {
+                Object::Num(n) if n.fract() == 0.0 && *n >= 0.0 => {
+                    lox_enum.value(*n as usize).unwrap_or(Object::Nil)
+                }
+                _ => Object::Nil,
+            })
+        }),
+        _ => return undefined(name, &format!("enum '{}'", lox_enum.name())),
+    };
+    Ok(Object::Native(Rc::new(native)))
+}
+
+pub fn enum_value_property(value: &Rc<LoxEnumValue>, name: &Token) -> Result<Object, LoxResult> {
+    match name.as_string().as_str() {
+        "name" => Ok(Object::Str(value.name().to_string())),
+        "ordinal" => Ok(Object::Num(value.ordinal() as f64)),
+        field => match value.field(field) {
+            Some(value) => Ok(value),
+            None => undefined(name, &format!("'{value}'")),
+        },
+    }
+}
+
+// `copy` on a data class instance: a new instance with the fields given as
+// arguments replaced, as in `point.copy(y: 5)`
+pub fn data_copy_method(instance: &Rc<LoxInstance>) -> Object {
+    let instance = Rc::clone(instance);
+    let fields = instance.class().fields().unwrap_or_default().to_vec();
+    Object::Native(Rc::new(NativeFunction::with_params(
+        "copy",
+        fields.clone(),
+        move |_, values| {
+            let copy = Rc::new(LoxInstance::new(instance.class()));
+            for (field, value) in fields.iter().zip(values) {
+                let value = value.or_else(|| instance.field(field));
+                copy.set(field, value.unwrap_or(Object::Nil));
+            }
+            Ok(Object::Instance(copy))
+        },
+    )))
+}
@@ -0,0 +1,250 @@
+use crate::callable::*;
+use crate::error::*;
+use crate::interpreter::*;
+use crate::lox_list::*;
+use crate::lox_map::*;
+use crate::lox_namespace::*;
+use crate::lox_regex::*;
+use crate::native_functions::*;
+use crate::native_methods::*;
+use crate::object::*;
+use crate::token::*;
+use std::rc::Rc;
+
+pub fn regex_namespace() -> LoxNamespace {
+    let mut regex = LoxNamespace::new("Regex");
+
+    // `flags` may contain `i` to ignore case, `m` for `^` and `$` to match at
+    // line breaks, and `s` for `.` to match newlines
+    regex.define(
+        "compile",
+        Object::Native(Rc::new(NativeFunction::with_optional(
+            "Regex.compile",
+            1,
+            1,
+            |_, args| {
+                let pattern = string_arg("Regex.compile", &args[0])?;
+                let flags = match args.get(1) {
+                    Some(flags) => string_arg("Regex.compile", flags)?,
+                    None => "",
+                };
+                LoxRegex::new(pattern, flags)
+                    .map(|regex| Object::Regex(Rc::new(regex)))
+                    .map_err(|e| LoxResult::native_error(&e))
+            },
+        ))),
+    );
+
+    // Backslashes every character that means something in a pattern, so
+    // the text matches itself
+    regex.define(
+        "escape",
+        Object::Native(Rc::new(NativeFunction::new(
+            "Regex.escape",
+            1,
+            |_, args| {
+                let text = string_arg("Regex.escape", &args[0])?;
+                let mut escaped = String::new();
+                for c in text.chars() {
+                    if "\\^$.|?*+()[]{}".contains(c) {
+                        escaped.push('\\');
+                    }
+                    escaped.push(c);
+                }
+                Ok(Object::Str(escaped))
+            },
+        ))),
+    );
+
+    regex
+}
+
+pub fn regex_method(regex: &Rc<LoxRegex>, name: &Token) -> Result<Object, LoxResult> {
+    let regex = Rc::clone(regex);
+    let native = match name.as_string().as_str() {
+        "test" => NativeFunction::new("test", 1, move |_, args| {
+            let text = chars_arg("test", &args[0])?;
+            Ok(Object::Bool(regex.find_at(&text, 0).is_some()))
+        }),
+        "find" => NativeFunction::new("find", 1, move |_, args| {
+            let text = chars_arg("find", &args[0])?;
+            Ok(match regex.find_at(&text, 0) {
+                Some(spans) => match_object(&text, &spans),
+                None => Object::Nil,
+            })
+        }),
+        "findAll" => NativeFunction::new("findAll", 1, move |_, args| {
+            let text = chars_arg("findAll", &args[0])?;
+            let matches = find_all(&regex, &text)
+                .iter()
+                .map(|spans| match_object(&text, spans))
+                .collect();
+            Ok(Object::List(Rc::new(LoxList::new(matches))))
+        }),
+        // Replaces every match with a string, in which `$0` stands for the
+        // whole match, `$1` to `$9` for its groups and `$$` for a dollar
+        // sign, or with the result of calling a function with the match
+        "replace" => NativeFunction::new("replace", 2, move |interpreter, args| {
+            let text = chars_arg("replace", &args[0])?;
+            let mut out = String::new();
+            let mut copied = 0;
+            for spans in find_all(&regex, &text) {
+                let (start, end) = spans[0].unwrap();
+                out.extend(&text[copied..start]);
+                out.push_str(&replacement(interpreter, &args[1], &text, &spans)?);
+                copied = end;
+            }
+            out.extend(&text[copied..]);
+            Ok(Object::Str(out))
+        }),
+        "groupCount" => {
+            let groups = regex.groups();
+            NativeFunction::new("groupCount", 0, move |_, _| Ok(Object::Num(groups as f64)))
+        }
+        "toString" => NativeFunction::new("toString", 0, move |_, _| {
+            Ok(Object::Str(regex.to_string()))
+        }),
+        _ => {
+            return Err(LoxResult::runtime_error(
+                name,
+                &format!("Undefined property '{}' on Regex.", name.as_string()),
+            ))
+        }
+    };
+    Ok(Object::Native(Rc::new(native)))
+}
+
+fn chars_arg(method: &str, arg: &Object) -> Result<Vec<char>, LoxResult> {
+    string_arg(method, arg).map(|s| s.chars().collect())
+}
+
+type Spans = Vec<Option<(usize, usize)>>;
+
+// Matches don't overlap, and an empty match moves the search on by one
+// character so that it can't be found again
+fn find_all(regex: &LoxRegex, text: &[char]) -> Vec<Spans> {
+    let mut matches = Vec::new();
+    let mut start = 0;
+    while start <= text.len() {
+        let Some(spans) = regex.find_at(text, start) else {
+            break;
+        };
+        let (from, to) = spans[0].unwrap();
+        start = if to == from { to + 1 } else { to };
+        matches.push(spans);
+    }
+    matches
+}
+
+fn span_text(text: &[char], span: Option<(usize, usize)>) -> Object {
+    match span {
+        Some((start, end)) => Object::Str(text[start..end].iter().collect()),
+        None => Object::Nil,
+    }
+}
+
+// A map of the matched `text`, where it `start`s and `end`s, and the text
+// of its `groups`, which is nil for a group that matched nothing
+fn match_object(text: &[char], spans: &Spans) -> Object {
+    let (start, end) = spans[0].unwrap();
+    let groups = spans[1..]
+        .iter()
+        .map(|span| span_text(text, *span))
+        .collect();
+    let map = LoxMap::new();
+    map.insert(Object::Str("text".to_string()), span_text(text, spans[0]));
+    map.insert(Object::Str("start".to_string()), Object::Num(start as f64));
+    map.insert(Object::Str("end".to_string()), Object::Num(end as f64));
+    map.insert(
+        Object::Str("groups".to_string()),
+        Object::List(Rc::new(LoxList::new(groups))),
+    );
+    Object::Map(Rc::new(map))
+}
+
+fn replacement(
+    interpreter: &Interpreter,
+    with: &Object,
+    text: &[char],
+    spans: &Spans,
+) -> Result<String, LoxResult> {
+    let callable: Rc<dyn LoxCallable> = match with {
+        Object::Str(template) => return expand(template, text, spans),
+        Object::Func(f) => f.clone(),
+        Object::Native(n) => n.clone(),
+        _ => {
+            return Err(LoxResult::native_error(&format!(
+                "'replace' expects a string or a function, not '{}'.",
+                with.type_name()
+            )))
+        }
+    };
+    let arguments = CallArguments::positional(vec![match_object(text, spans)]);
+    Ok(callable
+        .call_with(interpreter, arguments, None)?
+        .to_string())
+}
+
+fn expand(template: &str, text: &[char], spans: &Spans) -> Result<String, LoxResult> {
+    let mut out = String::new();
+    let mut chars = template.chars().peekable();
+    while let Some(c) = chars.next() {
+        if c != '$' {
+            out.push(c);
+            continue;
+        }
+        match chars.peek().copied() {
+            Some('$') => {
+                chars.next();
+                out.push('$');
+            }
+            Some(digit @ '0'..='9') => {
+                chars.next();
+                let group = digit as usize - '0' as usize;
+                let Some(span) = spans.get(group) else {
+                    let groups = match spans.len() - 1 {
+                        1 => "1 group".to_string(),
+                        n => format!("{n} groups"),
+                    };
+                    return Err(LoxResult::native_error(&format!(
+                        "'replace' refers to group ${group}, but the pattern has {groups}."
+                    )));
+                };
+                if let Object::Str(s) = span_text(text, *span) {
+                    out.push_str(&s);
+                }
+            }
+            _ => out.push('$'),
+        }
+    }
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn all(pattern: &str, text: &str) -> Vec<(usize, usize)> {
+        let regex = LoxRegex::new(pattern, "").unwrap();
+        let text = text.chars().collect::<Vec<char>>();
+        find_all(&regex, &text)
+            .into_iter()
+            .map(|spans| spans[0].unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn matches_do_not_overlap() {
+        assert_eq!(all("aa", "aaaaa"), [(0, 2), (2, 4)]);
+        assert_eq!(all("\\d+", "1 23 456"), [(0, 1), (2, 4), (5, 8)]);
+        assert_eq!(all("x", "abc"), []);
+    }
+
+    #[test]
+    fn empty_matches_move_on_by_one_character() {
+        assert_eq!(all("", "ab"), [(0, 0), (1, 1), (2, 2)]);
+        assert_eq!(all("a*", "baab"), [(0, 0), (1, 3), (3, 3), (4, 4)]);
+        assert_eq!(all("\\b", "hi yo"), [(0, 0), (2, 2), (3, 3), (5, 5)]);
+        assert_eq!(all("$", "é"), [(1, 1)]);
+    }
+}
@@ -1,7 +1,17 @@
 use crate::lox_class::*;
+use crate::lox_datetime::*;
+use crate::lox_enum::*;
 use crate::lox_function::*;
+use crate::lox_generator::*;
 use crate::lox_instance::*;
+use crate::lox_list::*;
+use crate::lox_map::*;
+use crate::lox_namespace::*;
+use crate::lox_range::*;
+use crate::lox_regex::*;
+use crate::lox_trait::*;
 use crate::native_functions::*;
+use std::cell::RefCell;
 use std::cmp::*;
 use std::fmt;
 use std::rc::Rc;
@@ -13,12 +23,58 @@ pub enum Object {
     Bool(bool),
     Func(Rc<LoxFunction>),
     Class(Rc<LoxClass>),
+    Trait(Rc<LoxTrait>),
     Instance(Rc<LoxInstance>),
-    Native(Rc<NativeClock>),
+    Enum(Rc<LoxEnum>),
+    EnumValue(Rc<LoxEnumValue>),
+    Native(Rc<NativeFunction>),
+    List(Rc<LoxList>),
+    Map(Rc<LoxMap>),
+    Range(Rc<LoxRange>),
+    Generator(Rc<LoxGenerator>),
+    Namespace(Rc<LoxNamespace>),
+    DateTime(Rc<LoxDateTime>),
+    Duration(Rc<LoxDuration>),
+    Regex(Rc<LoxRegex>),
     Nil,
     ArithmeticError,
 }
 
+impl Object {
+    pub fn type_name(&self) -> String {
+        match self {
+            Object::Num(_) => "number".to_string(),
+            Object::Str(_) => "string".to_string(),
+            Object::Bool(_) => "boolean".to_string(),
+            Object::Func(_) => "function".to_string(),
+            Object::Class(_) => "class".to_string(),
+            Object::Trait(_) => "trait".to_string(),
+            Object::Instance(i) => i.class_name(),
+            Object::Enum(_) => "enum".to_string(),
+            Object::EnumValue(v) => v.owner().name().to_string(),
+            Object::Native(_) => "native function".to_string(),
+            Object::List(_) => "list".to_string(),
+            Object::Map(_) => "map".to_string(),
+            Object::Range(_) => "range".to_string(),
+            Object::Generator(_) => "generator".to_string(),
+            Object::Namespace(_) => "namespace".to_string(),
+            Object::DateTime(_) => "datetime".to_string(),
+            Object::Duration(_) => "duration".to_string(),
+            Object::Regex(_) => "Regex".to_string(),
+            Object::Nil => "nil".to_string(),
+            Object::ArithmeticError => panic!("Should not be asking the type of this"),
+        }
+    }
+
+    // Like Display, but quotes strings so they stand out inside collections
+    pub fn repr(&self) -> String {
+        match self {
+            Object::Str(s) => format!("{s:?}"),
+            _ => self.to_string(),
+        }
+    }
+}
+
 impl fmt::Display for Object {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match self {
@@ -33,10 +89,77 @@ impl fmt::Display for Object {
             }
             Object::Func(func) => write!(f, "{}", func),
             Object::Class(c) => write!(f, "{}", c),
+            Object::Trait(t) => write!(f, "{}", t),
             Object::Instance(i) => write!(f, "{}", i),
+            Object::Enum(e) => write!(f, "{}", e),
+            Object::EnumValue(v) => write!(f, "{}", v),
             Object::Native(n) => write!(f, "{}", n),
+            Object::List(l) => print_once(f, Rc::as_ptr(l) as *const (), "[...]", |f| {
+                let elements = l
+                    .borrow()
+                    .iter()
+                    .map(|e| e.repr())
+                    .collect::<Vec<String>>()
+                    .join(", ");
+                write!(f, "[{elements}]")
+            }),
+            Object::Map(m) => write!(f, "{}", m),
+            Object::Range(r) => write!(f, "{}", r),
+            Object::Generator(g) => write!(f, "{}", g),
+            Object::Namespace(n) => write!(f, "{}", n),
+            Object::DateTime(d) => write!(f, "{}", d),
+            Object::Duration(d) => write!(f, "{}", d),
+            Object::Regex(r) => write!(f, "{}", r),
             Object::Nil => write!(f, "nil"),
             Object::ArithmeticError => panic!("Should not be trying to print this"),
         }
     }
 }
+
+thread_local! {
+    // The lists, maps and instances being printed, outermost first
+    static PRINTING: RefCell<Vec<*const ()>> = const { RefCell::new(Vec::new()) };
+}
+
+// Prints a container with `contents`, unless it is already being printed
+// further out, as it is when it contains itself; then prints `placeholder`
+pub fn print_once(
+    f: &mut fmt::Formatter,
+    container: *const (),
+    placeholder: &str,
+    contents: impl FnOnce(&mut fmt::Formatter) -> fmt::Result,
+) -> fmt::Result {
+    if PRINTING.with(|printing| printing.borrow().contains(&container)) {
+        return write!(f, "{placeholder}");
+    }
+    PRINTING.with(|printing| printing.borrow_mut().push(container));
+    let result = contents(f);
+    PRINTING.with(|printing| printing.borrow_mut().pop());
+    result
+}
+
+// Drops values one at a time. Lists, maps and instances that nothing else
+// refers to are emptied onto the same pile first, so dropping a deeply
+// nested value can't overflow the stack.
+pub fn release(mut values: Vec<Object>) {
+    while let Some(value) = values.pop() {
+        match value {
+            Object::List(list) => {
+                if let Some(list) = Rc::into_inner(list) {
+                    values.extend(list.take());
+                }
+            }
+            Object::Map(map) => {
+                if let Some(map) = Rc::into_inner(map) {
+                    values.extend(map.take_values());
+                }
+            }
+            Object::Instance(instance) => {
+                if let Some(instance) = Rc::into_inner(instance) {
+                    values.extend(instance.take_fields());
+                }
+            }
+            _ => {}
+        }
+    }
+}
@@ -1,5 +1,6 @@
 use crate::expr::*;
 use crate::object::*;
+use crate::pattern::*;
 use crate::stmt::*;
 use crate::token::*;
 use crate::token_type::*;
@@ -13,7 +14,7 @@ pub struct Parser<'a> {
 }
 
 impl<'a> Parser<'a> {
-    pub fn new(tokens: &[Token]) -> Parser {
+    pub fn new(tokens: &[Token]) -> Parser<'_> {
         Parser {
             tokens,
             current: 0,
@@ -41,11 +42,23 @@ impl<'a> Parser<'a> {
 
     fn declaration(&mut self) -> Result<Rc<Stmt>, LoxResult> {
         let result = if self.is_match(&[TokenType::Class]) {
-            self.class_declaration()
+            self.class_declaration(false)
+        } else if self.check_ahead(&[TokenType::Identifier, TokenType::Class])
+            && self.peek().as_string() == "data"
+        {
+            self.advance();
+            self.advance();
+            self.class_declaration(true)
+        } else if self.is_match(&[TokenType::Trait]) {
+            self.trait_declaration()
+        } else if self.is_match(&[TokenType::Enum]) {
+            self.enum_declaration()
         } else if self.is_match(&[TokenType::Fun]) {
             self.function("function")
         } else if self.is_match(&[TokenType::Var]) {
-            self.var_declaration()
+            self.var_declaration(false)
+        } else if self.is_match(&[TokenType::Const]) {
+            self.var_declaration(true)
         } else {
             self.statement()
         };
@@ -57,9 +70,27 @@ impl<'a> Parser<'a> {
         result
     }
 
-    fn class_declaration(&mut self) -> Result<Rc<Stmt>, LoxResult> {
+    fn class_declaration(&mut self, is_data: bool) -> Result<Rc<Stmt>, LoxResult> {
         let name = self.consume(TokenType::Identifier, "Expect class name.")?;
 
+        // A data class lists its fields up front: `data class Point(x, y);`
+        let fields = if is_data {
+            self.consume(TokenType::LeftParen, "Expect '(' after data class name.")?;
+            let mut fields = Vec::new();
+            if !self.check(TokenType::RightParen) {
+                loop {
+                    fields.push(self.consume(TokenType::Identifier, "Expect field name.")?);
+                    if !self.is_match(&[TokenType::Comma]) {
+                        break;
+                    }
+                }
+            }
+            self.consume(TokenType::RightParen, "Expect ')' after fields.")?;
+            Some(Rc::new(fields))
+        } else {
+            None
+        };
+
         let superclass = if self.is_match(&[TokenType::Less]) {
             self.consume(TokenType::Identifier, "Expect superclass name.")?;
             Some(Rc::new(Expr::Variable(Rc::new(VariableExpr {
@@ -69,21 +100,125 @@ impl<'a> Parser<'a> {
             None
         };
 
-        self.consume(TokenType::LeftBrace, "Expect '{' before class body.")?;
-
-        let mut methods = Vec::new();
-        while !self.check(TokenType::RightBrace) && !self.is_at_end() {
-            methods.push(self.function("method")?);
+        let mut traits = Vec::new();
+        if self.is_match(&[TokenType::With]) {
+            loop {
+                self.consume(TokenType::Identifier, "Expect trait name.")?;
+                traits.push(Rc::new(Expr::Variable(Rc::new(VariableExpr {
+                    name: self.previous().dup(),
+                }))));
+                if !self.is_match(&[TokenType::Comma]) {
+                    break;
+                }
+            }
         }
-        self.consume(TokenType::RightBrace, "Expect '}' after class body.")?;
+
+        let (methods, abstract_methods) = if is_data && self.is_match(&[TokenType::SemiColon]) {
+            (Vec::new(), Vec::new())
+        } else {
+            self.consume(TokenType::LeftBrace, "Expect '{' before class body.")?;
+            self.class_body("class")?
+        };
 
         Ok(Rc::new(Stmt::Class(Rc::new(ClassStmt {
             name,
+            fields,
             superclass,
+            traits: Rc::new(traits),
             methods: Rc::new(methods),
+            abstract_methods: Rc::new(abstract_methods),
+        }))))
+    }
+
+    fn trait_declaration(&mut self) -> Result<Rc<Stmt>, LoxResult> {
+        let name = self.consume(TokenType::Identifier, "Expect trait name.")?;
+        self.consume(TokenType::LeftBrace, "Expect '{' before trait body.")?;
+        let (methods, abstract_methods) = self.class_body("trait")?;
+
+        Ok(Rc::new(Stmt::Trait(Rc::new(TraitStmt {
+            name,
+            methods: Rc::new(methods),
+            abstract_methods: Rc::new(abstract_methods),
+        }))))
+    }
+
+    fn enum_declaration(&mut self) -> Result<Rc<Stmt>, LoxResult> {
+        let name = self.consume(TokenType::Identifier, "Expect enum name.")?;
+        self.consume(TokenType::LeftBrace, "Expect '{' before enum body.")?;
+
+        let mut variants = Vec::new();
+        let mut payloads = Vec::new();
+        while !self.check(TokenType::RightBrace) && !self.is_at_end() {
+            variants.push(self.consume(TokenType::Identifier, "Expect variant name.")?);
+            payloads.push(if self.is_match(&[TokenType::LeftParen]) {
+                let mut fields = Vec::new();
+                if !self.check(TokenType::RightParen) {
+                    loop {
+                        fields.push(self.consume(TokenType::Identifier, "Expect field name.")?);
+                        if !self.is_match(&[TokenType::Comma]) {
+                            break;
+                        }
+                    }
+                }
+                self.consume(TokenType::RightParen, "Expect ')' after variant fields.")?;
+                Some(fields)
+            } else {
+                None
+            });
+
+            if !self.is_match(&[TokenType::Comma]) {
+                break;
+            }
+        }
+
+        self.consume(TokenType::RightBrace, "Expect '}' after enum body.")?;
+
+        Ok(Rc::new(Stmt::Enum(Rc::new(EnumStmt {
+            name,
+            variants: Rc::new(variants),
+            payloads: Rc::new(payloads),
         }))))
     }
 
+    fn class_body(&mut self, kind: &str) -> Result<(Vec<Rc<Stmt>>, Vec<Token>), LoxResult> {
+        let mut methods = Vec::new();
+        let mut abstract_methods = Vec::new();
+
+        while !self.check(TokenType::RightBrace) && !self.is_at_end() {
+            if self.is_match(&[TokenType::Abstract]) {
+                abstract_methods.push(self.abstract_method()?);
+            } else {
+                methods.push(self.function("method")?);
+            }
+        }
+
+        self.consume(
+            TokenType::RightBrace,
+            &format!("Expect '}}' after {kind} body."),
+        )?;
+        Ok((methods, abstract_methods))
+    }
+
+    // Abstract methods only declare a signature, the parameters are just documentation
+    fn abstract_method(&mut self) -> Result<Token, LoxResult> {
+        let name = self.consume(TokenType::Identifier, "Expect abstract method name.")?;
+        self.consume(TokenType::LeftParen, "Expect '(' after method name.")?;
+        if !self.check(TokenType::RightParen) {
+            loop {
+                self.consume(TokenType::Identifier, "Expect parameter name.")?;
+                if !self.is_match(&[TokenType::Comma]) {
+                    break;
+                }
+            }
+        }
+        self.consume(TokenType::RightParen, "Expect ')' after parameters.")?;
+        self.consume(
+            TokenType::SemiColon,
+            "Expect ';' after abstract method declaration.",
+        )?;
+        Ok(name)
+    }
+
     fn statement(&mut self) -> Result<Rc<Stmt>, LoxResult> {
         if self.is_match(&[TokenType::Break]) {
             let token = self.previous().dup();
@@ -96,6 +231,9 @@ impl<'a> Parser<'a> {
         if self.is_match(&[TokenType::If]) {
             return Ok(Rc::new(self.if_statement()?));
         }
+        if self.is_match(&[TokenType::Match]) {
+            return Ok(Rc::new(self.match_statement()?));
+        }
         if self.is_match(&[TokenType::Print]) {
             return Ok(Rc::new(self.print_statement()?));
         }
@@ -105,6 +243,12 @@ impl<'a> Parser<'a> {
         if self.is_match(&[TokenType::While]) {
             return Ok(Rc::new(self.while_statement()?));
         }
+        if self.is_match(&[TokenType::Yield]) {
+            return Ok(Rc::new(self.yield_statement()?));
+        }
+        if self.is_destructuring_assignment() {
+            return self.destructure(None);
+        }
         if self.is_match(&[TokenType::LeftBrace]) {
             return Ok(Rc::new(Stmt::Block(Rc::new(BlockStmt {
                 statements: Rc::new(self.block()?),
@@ -116,10 +260,16 @@ impl<'a> Parser<'a> {
     fn for_statement(&mut self) -> Result<Rc<Stmt>, LoxResult> {
         self.consume(TokenType::LeftParen, "Expect '(' after 'for'.")?;
 
+        if self.check_ahead(&[TokenType::Identifier, TokenType::In])
+            || self.check_ahead(&[TokenType::Var, TokenType::Identifier, TokenType::In])
+        {
+            return self.for_in_statement();
+        }
+
         let initializer = if self.is_match(&[TokenType::SemiColon]) {
             None
         } else if self.is_match(&[TokenType::Var]) {
-            Some(self.var_declaration()?)
+            Some(self.var_declaration(false)?)
         } else {
             Some(self.expression_statement()?)
         };
@@ -173,6 +323,21 @@ impl<'a> Parser<'a> {
         Ok(body)
     }
 
+    fn for_in_statement(&mut self) -> Result<Rc<Stmt>, LoxResult> {
+        self.is_match(&[TokenType::Var]);
+        let name = self.consume(TokenType::Identifier, "Expect loop variable name.")?;
+        self.consume(TokenType::In, "Expect 'in' after loop variable.")?;
+        let iterable = Rc::new(self.expression()?);
+        self.consume(TokenType::RightParen, "Expect ')' after for-in clause.")?;
+        let body = self.statement()?;
+
+        Ok(Rc::new(Stmt::ForIn(Rc::new(ForInStmt {
+            name,
+            iterable,
+            body,
+        }))))
+    }
+
     fn if_statement(&mut self) -> Result<Stmt, LoxResult> {
         self.consume(TokenType::LeftParen, "Expect '(' after 'if'.")?;
         let condition = Rc::new(self.expression()?);
@@ -192,6 +357,301 @@ impl<'a> Parser<'a> {
         })))
     }
 
+    fn match_statement(&mut self) -> Result<Stmt, LoxResult> {
+        let keyword = self.previous().dup();
+        self.consume(TokenType::LeftParen, "Expect '(' after 'match'.")?;
+        let subject = Rc::new(self.expression()?);
+        self.consume(TokenType::RightParen, "Expect ')' after match subject.")?;
+        self.consume(TokenType::LeftBrace, "Expect '{' before match cases.")?;
+
+        let mut patterns = Vec::new();
+        let mut guards = Vec::new();
+        let mut bodies = Vec::new();
+        while !self.check(TokenType::RightBrace) && !self.is_at_end() {
+            self.consume(TokenType::Case, "Expect 'case' before pattern.")?;
+            patterns.push(self.pattern()?);
+            guards.push(if self.is_match(&[TokenType::If]) {
+                Some(Rc::new(self.expression()?))
+            } else {
+                None
+            });
+            self.consume(TokenType::Colon, "Expect ':' after pattern.")?;
+            bodies.push(self.statement()?);
+        }
+
+        self.consume(TokenType::RightBrace, "Expect '}' after match cases.")?;
+
+        Ok(Stmt::Match(Rc::new(MatchStmt {
+            keyword,
+            subject,
+            patterns: Rc::new(patterns),
+            guards: Rc::new(guards),
+            bodies: Rc::new(bodies),
+        })))
+    }
+
+    fn pattern(&mut self) -> Result<Rc<Pattern>, LoxResult> {
+        let first = self.simple_pattern()?;
+        if !self.check(TokenType::Pipe) {
+            return Ok(first);
+        }
+
+        let bar = self.peek().dup();
+        let mut alternatives = vec![first];
+        while self.is_match(&[TokenType::Pipe]) {
+            alternatives.push(self.simple_pattern()?);
+        }
+        Ok(Rc::new(Pattern::Alternative(Rc::new(AlternativePattern {
+            bar,
+            alternatives,
+        }))))
+    }
+
+    fn simple_pattern(&mut self) -> Result<Rc<Pattern>, LoxResult> {
+        let literal = if self.is_match(&[TokenType::Number, TokenType::String]) {
+            self.previous().literal.clone()
+        } else if self.is_match(&[TokenType::True]) {
+            Some(Object::Bool(true))
+        } else if self.is_match(&[TokenType::False]) {
+            Some(Object::Bool(false))
+        } else if self.is_match(&[TokenType::Nil]) {
+            Some(Object::Nil)
+        } else if self.is_match(&[TokenType::Minus]) {
+            let number = self.consume(TokenType::Number, "Expect number after '-' in pattern.")?;
+            match number.literal {
+                Some(Object::Num(n)) => Some(Object::Num(-n)),
+                _ => None,
+            }
+        } else {
+            None
+        };
+        if let Some(value) = literal {
+            return Ok(Rc::new(Pattern::Literal(Rc::new(LiteralPattern {
+                token: self.previous().dup(),
+                value,
+            }))));
+        }
+
+        if self.is_match(&[TokenType::LeftBracket]) {
+            return self.list_pattern();
+        }
+        if self.is_match(&[TokenType::LeftBrace]) {
+            return self.record_pattern();
+        }
+
+        let name = self.consume(TokenType::Identifier, "Expect pattern.")?;
+        if name.as_string() == "_" {
+            return Ok(Rc::new(Pattern::Wildcard(Rc::new(WildcardPattern {
+                token: name,
+            }))));
+        }
+
+        // A dotted path names a value or class, as in `Color.Red`
+        let mut last = name.dup();
+        let mut path = Expr::Variable(Rc::new(VariableExpr { name }));
+        while self.is_match(&[TokenType::Dot]) {
+            last = self.consume(TokenType::Identifier, "Expect name after '.'.")?;
+            path = Expr::Get(Rc::new(GetExpr {
+                object: Rc::new(path),
+                name: last.dup(),
+            }));
+        }
+
+        let mut arguments = Vec::new();
+        let mut fields = Vec::new();
+        let mut field_patterns = Vec::new();
+        if self.is_match(&[TokenType::LeftParen]) {
+            if !self.check(TokenType::RightParen) {
+                loop {
+                    arguments.push(self.pattern()?);
+                    if !self.is_match(&[TokenType::Comma]) {
+                        break;
+                    }
+                }
+            }
+            self.consume(TokenType::RightParen, "Expect ')' after patterns.")?;
+        } else if self.is_match(&[TokenType::LeftBrace]) {
+            if !self.check(TokenType::RightBrace) {
+                loop {
+                    let field = self.consume(TokenType::Identifier, "Expect field name.")?;
+                    field_patterns.push(if self.is_match(&[TokenType::Colon]) {
+                        self.pattern()?
+                    } else {
+                        Rc::new(Pattern::Binding(Rc::new(BindingPattern {
+                            name: field.dup(),
+                        })))
+                    });
+                    fields.push(field);
+                    if !self.is_match(&[TokenType::Comma]) {
+                        break;
+                    }
+                }
+            }
+            self.consume(TokenType::RightBrace, "Expect '}' after field patterns.")?;
+        } else if let Expr::Variable(v) = path {
+            return Ok(Rc::new(Pattern::Binding(Rc::new(BindingPattern {
+                name: v.name.dup(),
+            }))));
+        } else {
+            return Ok(Rc::new(Pattern::Value(Rc::new(ValuePattern {
+                path: Rc::new(path),
+                name: last,
+            }))));
+        }
+
+        Ok(Rc::new(Pattern::Class(Rc::new(ClassPattern {
+            path: Rc::new(path),
+            name: last,
+            arguments,
+            fields,
+            field_patterns,
+        }))))
+    }
+
+    // `[first, second = 2, ...others]`
+    fn list_pattern(&mut self) -> Result<Rc<Pattern>, LoxResult> {
+        let bracket = self.previous().dup();
+        let mut elements = Vec::new();
+        let mut defaults = Vec::new();
+        let mut rest = None;
+
+        if !self.check(TokenType::RightBracket) {
+            loop {
+                if self.is_match(&[TokenType::Ellipsis]) {
+                    rest = Some(self.simple_pattern()?);
+                    break;
+                }
+                elements.push(self.pattern()?);
+                let default = self.pattern_default()?;
+                if default.is_none() && defaults.iter().any(Option::is_some) {
+                    let previous = self.previous().dup();
+                    return Err(self.error(
+                        &previous,
+                        "An element without a default can't follow one with a default.",
+                    ));
+                }
+                defaults.push(default);
+                if !self.is_match(&[TokenType::Comma]) {
+                    break;
+                }
+            }
+        }
+
+        self.consume(TokenType::RightBracket, "Expect ']' after list pattern.")?;
+        Ok(Rc::new(Pattern::List(Rc::new(ListPattern {
+            bracket,
+            elements,
+            defaults,
+            rest,
+        }))))
+    }
+
+    // `{x, y: [a, b], "some key": z, w = 0}`
+    fn record_pattern(&mut self) -> Result<Rc<Pattern>, LoxResult> {
+        let brace = self.previous().dup();
+        let mut fields = Vec::new();
+        let mut patterns = Vec::new();
+        let mut defaults = Vec::new();
+
+        if !self.check(TokenType::RightBrace) {
+            loop {
+                let field = if self.is_match(&[TokenType::Identifier, TokenType::String]) {
+                    self.previous().dup()
+                } else {
+                    let peek = self.peek().dup();
+                    return Err(self.error(&peek, "Expect field name."));
+                };
+
+                patterns.push(if self.is_match(&[TokenType::Colon]) {
+                    self.pattern()?
+                } else if field.is(TokenType::Identifier) {
+                    Rc::new(Pattern::Binding(Rc::new(BindingPattern {
+                        name: field.dup(),
+                    })))
+                } else {
+                    let peek = self.peek().dup();
+                    return Err(self.error(&peek, "Expect ':' after quoted field name."));
+                });
+                defaults.push(self.pattern_default()?);
+                fields.push(field);
+
+                if !self.is_match(&[TokenType::Comma]) {
+                    break;
+                }
+            }
+        }
+
+        self.consume(TokenType::RightBrace, "Expect '}' after record pattern.")?;
+        Ok(Rc::new(Pattern::Record(Rc::new(RecordPattern {
+            brace,
+            fields,
+            patterns,
+            defaults,
+        }))))
+    }
+
+    fn pattern_default(&mut self) -> Result<Option<Rc<Expr>>, LoxResult> {
+        if self.is_match(&[TokenType::Assign]) {
+            Ok(Some(Rc::new(self.expression()?)))
+        } else {
+            Ok(None)
+        }
+    }
+
+    // `var [a, b] = pair;` declares, `[a, b] = [b, a];` assigns
+    fn destructure(&mut self, keyword: Option<Token>) -> Result<Rc<Stmt>, LoxResult> {
+        let pattern = self.pattern()?;
+        let equals = self.consume(TokenType::Assign, "Expect '=' after pattern.")?;
+        let value = Rc::new(self.expression()?);
+        self.consume(TokenType::SemiColon, "Expect ';' after destructuring.")?;
+        Ok(Rc::new(Stmt::Destructure(Rc::new(DestructureStmt {
+            keyword,
+            equals,
+            pattern,
+            value,
+        }))))
+    }
+
+    // A bracketed or braced pattern followed by '=' is a destructuring
+    // assignment; as an expression or block it could not be assigned to.
+    // The lookahead gives up at the first ';', which no pattern contains, and
+    // at a brace not followed by a field name, so blocks are rejected without
+    // scanning the statements nested in them.
+    fn is_destructuring_assignment(&self) -> bool {
+        let pattern_shaped = match self.peek().token_type() {
+            TokenType::LeftBracket => true,
+            TokenType::LeftBrace => self.tokens.get(self.current + 1).is_some_and(|t| {
+                matches!(
+                    t.token_type(),
+                    TokenType::Identifier | TokenType::String | TokenType::RightBrace
+                )
+            }),
+            _ => false,
+        };
+        if !pattern_shaped {
+            return false;
+        }
+
+        let mut depth = 0;
+        for (i, token) in self.tokens[self.current..].iter().enumerate() {
+            match token.token_type() {
+                TokenType::LeftBracket | TokenType::LeftBrace | TokenType::LeftParen => depth += 1,
+                TokenType::RightBracket | TokenType::RightBrace | TokenType::RightParen => {
+                    depth -= 1;
+                    if depth == 0 {
+                        return self
+                            .tokens
+                            .get(self.current + i + 1)
+                            .is_some_and(|t| t.is(TokenType::Assign));
+                    }
+                }
+                TokenType::SemiColon | TokenType::Eof => break,
+                _ => {}
+            }
+        }
+        false
+    }
+
     fn print_statement(&mut self) -> Result<Stmt, LoxResult> {
         let value = Rc::new(self.expression()?);
         self.consume(TokenType::SemiColon, "Expect ';' after value.")?;
@@ -210,10 +670,18 @@ impl<'a> Parser<'a> {
         Ok(Stmt::Return(Rc::new(ReturnStmt { keyword, value })))
     }
 
-    fn var_declaration(&mut self) -> Result<Rc<Stmt>, LoxResult> {
+    fn var_declaration(&mut self, is_const: bool) -> Result<Rc<Stmt>, LoxResult> {
+        if self.check(TokenType::LeftBracket) || self.check(TokenType::LeftBrace) {
+            let keyword = self.previous().dup();
+            return self.destructure(Some(keyword));
+        }
+
         let name = self.consume(TokenType::Identifier, "Expect variable name.")?;
         let initializer = if self.is_match(&[TokenType::Assign]) {
             Some(Rc::new(self.expression()?))
+        } else if is_const {
+            let peek = self.peek().dup();
+            return Err(self.error(&peek, "Expect '=' after constant name."));
         } else {
             None
         };
@@ -222,7 +690,11 @@ impl<'a> Parser<'a> {
             TokenType::SemiColon,
             "Expect ';' after variable declaration.",
         )?;
-        Ok(Rc::new(Stmt::Var(Rc::new(VarStmt { name, initializer }))))
+        Ok(Rc::new(Stmt::Var(Rc::new(VarStmt {
+            name,
+            initializer,
+            is_const,
+        }))))
     }
 
     fn while_statement(&mut self) -> Result<Stmt, LoxResult> {
@@ -233,6 +705,18 @@ impl<'a> Parser<'a> {
         Ok(Stmt::While(Rc::new(WhileStmt { condition, body })))
     }
 
+    fn yield_statement(&mut self) -> Result<Stmt, LoxResult> {
+        let keyword = self.previous().dup();
+        let value = if self.check(TokenType::SemiColon) {
+            None
+        } else {
+            Some(Rc::new(self.expression()?))
+        };
+
+        self.consume(TokenType::SemiColon, "Expect ';' after yield value.")?;
+        Ok(Stmt::Yield(Rc::new(YieldStmt { keyword, value })))
+    }
+
     fn expression_statement(&mut self) -> Result<Rc<Stmt>, LoxResult> {
         let expr = Rc::new(self.expression()?);
         self.consume(TokenType::SemiColon, "Expect ';' after value.")?;
@@ -242,22 +726,53 @@ impl<'a> Parser<'a> {
     }
 
     fn function(&mut self, kind: &str) -> Result<Rc<Stmt>, LoxResult> {
-        let name = self.consume(TokenType::Identifier, &format!("Expect {kind} name."))?;
+        let is_generator = self.is_match(&[TokenType::Star]);
+        let name = if kind == "method" {
+            self.member_name(&format!("Expect {kind} name."))?
+        } else {
+            self.consume(TokenType::Identifier, &format!("Expect {kind} name."))?
+        };
         self.consume(
             TokenType::LeftParen,
             &format!("Expect '(' after {kind} name."),
         )?;
 
         let mut params = Vec::new();
+        let mut defaults = Vec::new();
+        let mut rest = None;
         if !self.check(TokenType::RightParen) {
-            params.push(self.consume(TokenType::Identifier, "Expect parameter name.")?);
-
-            while self.is_match(&[TokenType::Comma]) {
+            loop {
                 if params.len() >= 255 && !self.had_error {
                     let peek = self.peek().dup();
                     self.error(&peek, "Can't have more than 255 parameters.");
                 }
-                params.push(self.consume(TokenType::Identifier, "Expect parameter name.")?);
+
+                if self.is_match(&[TokenType::Ellipsis]) {
+                    rest =
+                        Some(self.consume(TokenType::Identifier, "Expect rest parameter name.")?);
+                    if self.check(TokenType::Comma) {
+                        let peek = self.peek().dup();
+                        return Err(self.error(&peek, "The rest parameter must be the last one."));
+                    }
+                    break;
+                }
+
+                let param = self.consume(TokenType::Identifier, "Expect parameter name.")?;
+                if self.is_match(&[TokenType::Assign]) {
+                    defaults.push(Some(Rc::new(self.expression()?)));
+                } else if defaults.iter().any(Option::is_some) {
+                    return Err(self.error(
+                        &param,
+                        "A parameter without a default value can't follow one with a default.",
+                    ));
+                } else {
+                    defaults.push(None);
+                }
+                params.push(param);
+
+                if !self.is_match(&[TokenType::Comma]) {
+                    break;
+                }
             }
         }
 
@@ -272,7 +787,10 @@ impl<'a> Parser<'a> {
         Ok(Rc::new(Stmt::Function(Rc::new(FunctionStmt {
             name,
             params: Rc::new(params),
+            defaults: Rc::new(defaults),
+            rest,
             body: Rc::new(body),
+            is_generator,
         }))))
     }
 
@@ -288,7 +806,7 @@ impl<'a> Parser<'a> {
     }
 
     fn assignment(&mut self) -> Result<Expr, LoxResult> {
-        let expr = self.or()?;
+        let expr = self.pipeline()?;
 
         if self.is_match(&[TokenType::Assign]) {
             let equals = self.previous().dup();
@@ -305,6 +823,13 @@ impl<'a> Parser<'a> {
                     name: get.name.dup(),
                     value: Rc::new(value),
                 })));
+            } else if let Expr::Index(index) = expr {
+                return Ok(Expr::SetIndex(Rc::new(SetIndexExpr {
+                    object: Rc::clone(&index.object),
+                    bracket: index.bracket.dup(),
+                    index: Rc::clone(&index.index),
+                    value: Rc::new(value),
+                })));
             } else {
                 self.error(&equals, "Invalid assignment target.");
             }
@@ -313,6 +838,55 @@ impl<'a> Parser<'a> {
         Ok(expr)
     }
 
+    // `a |> f(b)` is the call `f(a, b)`, and `a |> f` is `f(a)`
+    fn pipeline(&mut self) -> Result<Expr, LoxResult> {
+        let mut expr = self.coalesce()?;
+
+        while self.is_match(&[TokenType::PipeGreater]) {
+            let operator = self.previous().dup();
+            let value = Rc::new(expr);
+            let target = self.coalesce()?;
+            expr = match &target {
+                Expr::Call(call) => Expr::Call(Rc::new(piped_call(value, call))),
+                // `x |> a?.m()` calls `a?.m(x)`, short-circuiting as usual
+                Expr::OptionalChain(optional) if matches!(*optional.chain, Expr::Call(_)) => {
+                    let Expr::Call(call) = optional.chain.as_ref() else {
+                        unreachable!("checked by the guard")
+                    };
+                    Expr::OptionalChain(Rc::new(OptionalChainExpr {
+                        chain: Rc::new(Expr::Call(Rc::new(piped_call(value, call)))),
+                    }))
+                }
+                _ => Expr::Call(Rc::new(CallExpr {
+                    callee: Rc::new(target),
+                    paren: operator,
+                    arguments: vec![value],
+                    names: Vec::new(),
+                    named_arguments: Vec::new(),
+                })),
+            };
+        }
+
+        Ok(expr)
+    }
+
+    // `a ?? b` binds more loosely than `or`, so `a ?? b or c` is `a ?? (b or c)`
+    fn coalesce(&mut self) -> Result<Expr, LoxResult> {
+        let mut expr = self.or()?;
+
+        while self.is_match(&[TokenType::QuestionQuestion]) {
+            let operator = self.previous().dup();
+            let right = Rc::new(self.or()?);
+            expr = Expr::Logical(Rc::new(LogicalExpr {
+                left: Rc::new(expr),
+                operator,
+                right,
+            }))
+        }
+
+        Ok(expr)
+    }
+
     fn or(&mut self) -> Result<Expr, LoxResult> {
         let mut expr = self.and()?;
 
@@ -362,7 +936,7 @@ impl<'a> Parser<'a> {
     }
 
     fn comparison(&mut self) -> Result<Expr, LoxResult> {
-        let mut expr = self.term()?;
+        let mut expr = self.range()?;
 
         while self.is_match(&[
             TokenType::Greater,
@@ -371,7 +945,7 @@ impl<'a> Parser<'a> {
             TokenType::LessEqual,
         ]) {
             let operator = self.previous().dup();
-            let right = self.term()?;
+            let right = self.range()?;
 
             expr = Expr::Binary(Rc::new(BinaryExpr {
                 left: Rc::new(expr),
@@ -383,6 +957,30 @@ impl<'a> Parser<'a> {
         Ok(expr)
     }
 
+    // `a..b`, `a..=b`, and either with `step s`
+    fn range(&mut self) -> Result<Expr, LoxResult> {
+        let expr = self.term()?;
+
+        if self.is_match(&[TokenType::DotDot, TokenType::DotDotEqual]) {
+            let operator = self.previous().dup();
+            let end = self.term()?;
+            let step = if self.check(TokenType::Identifier) && self.peek().as_string() == "step" {
+                self.advance();
+                Some(Rc::new(self.term()?))
+            } else {
+                None
+            };
+            return Ok(Expr::Range(Rc::new(RangeExpr {
+                start: Rc::new(expr),
+                operator,
+                end: Rc::new(end),
+                step,
+            })));
+        }
+
+        Ok(expr)
+    }
+
     fn term(&mut self) -> Result<Expr, LoxResult> {
         let mut expr = self.factor()?;
 
@@ -432,18 +1030,33 @@ impl<'a> Parser<'a> {
 
     fn finish_call(&mut self, callee: &Rc<Expr>) -> Result<Expr, LoxResult> {
         let mut arguments = Vec::new();
+        let mut names = Vec::new();
+        let mut named_arguments = Vec::new();
 
         if !self.check(TokenType::RightParen) {
-            arguments.push(Rc::new(self.expression()?));
-            while self.is_match(&[TokenType::Comma]) {
-                if arguments.len() >= 255 {
-                    if !self.had_error {
-                        let peek = self.peek().dup();
-                        self.error(&peek, "Can't have more than 255 arguments");
-                    }
+            loop {
+                if arguments.len() + named_arguments.len() >= 255 && !self.had_error {
+                    let peek = self.peek().dup();
+                    self.error(&peek, "Can't have more than 255 arguments");
+                }
+
+                // Named arguments look like `name: value`, and come last
+                if self.check_ahead(&[TokenType::Identifier, TokenType::Colon]) {
+                    names.push(self.advance().dup());
+                    self.advance();
+                    named_arguments.push(Rc::new(self.expression()?));
+                } else if !names.is_empty() {
+                    let peek = self.peek().dup();
+                    return Err(
+                        self.error(&peek, "Positional arguments can't follow named arguments.")
+                    );
                 } else {
                     arguments.push(Rc::new(self.expression()?));
                 }
+
+                if !self.is_match(&[TokenType::Comma]) {
+                    break;
+                }
             }
         }
 
@@ -453,26 +1066,51 @@ impl<'a> Parser<'a> {
             callee: Rc::clone(callee),
             paren,
             arguments,
+            names,
+            named_arguments,
         })))
     }
 
     fn call(&mut self) -> Result<Expr, LoxResult> {
         let mut expr = self.primary()?;
+        let mut optional = false;
 
         loop {
             if self.is_match(&[TokenType::LeftParen]) {
                 expr = self.finish_call(&Rc::new(expr))?;
             } else if self.is_match(&[TokenType::Dot]) {
-                let name = self.consume(TokenType::Identifier, "Expect property name after '.'")?;
+                let name = self.member_name("Expect property name after '.'")?;
                 expr = Expr::Get(Rc::new(GetExpr {
                     object: Rc::new(expr),
                     name,
                 }));
+            } else if self.is_match(&[TokenType::QuestionDot]) {
+                let name = self.member_name("Expect property name after '?.'")?;
+                expr = Expr::OptionalGet(Rc::new(OptionalGetExpr {
+                    object: Rc::new(expr),
+                    name,
+                }));
+                optional = true;
+            } else if self.is_match(&[TokenType::LeftBracket]) {
+                let index = self.expression()?;
+                let bracket = self.consume(TokenType::RightBracket, "Expect ']' after index.")?;
+                expr = Expr::Index(Rc::new(IndexExpr {
+                    object: Rc::new(expr),
+                    bracket,
+                    index: Rc::new(index),
+                }));
             } else {
                 break;
             }
         }
 
+        // A `?.` that meets nil skips everything after it up to here
+        if optional {
+            expr = Expr::OptionalChain(Rc::new(OptionalChainExpr {
+                chain: Rc::new(expr),
+            }));
+        }
+
         Ok(expr)
     }
 
@@ -520,6 +1158,14 @@ impl<'a> Parser<'a> {
             })));
         }
 
+        if self.is_match(&[TokenType::LeftBracket]) {
+            return self.list();
+        }
+
+        if self.is_match(&[TokenType::LeftBrace]) {
+            return self.map();
+        }
+
         if self.is_match(&[TokenType::LeftParen]) {
             let expr = self.expression()?;
             self.consume(TokenType::RightParen, "Expect ')' after expression")?;
@@ -532,6 +1178,46 @@ impl<'a> Parser<'a> {
         Err(LoxResult::parse_error(&peek, "Expect expression"))
     }
 
+    fn list(&mut self) -> Result<Expr, LoxResult> {
+        let mut elements = Vec::new();
+
+        if !self.check(TokenType::RightBracket) {
+            loop {
+                elements.push(Rc::new(self.expression()?));
+                if !self.is_match(&[TokenType::Comma]) {
+                    break;
+                }
+            }
+        }
+
+        self.consume(TokenType::RightBracket, "Expect ']' after list elements.")?;
+        Ok(Expr::List(Rc::new(ListExpr { elements })))
+    }
+
+    fn map(&mut self) -> Result<Expr, LoxResult> {
+        let brace = self.previous().dup();
+        let mut keys = Vec::new();
+        let mut values = Vec::new();
+
+        if !self.check(TokenType::RightBrace) {
+            loop {
+                keys.push(Rc::new(self.expression()?));
+                self.consume(TokenType::Colon, "Expect ':' after map key.")?;
+                values.push(Rc::new(self.expression()?));
+                if !self.is_match(&[TokenType::Comma]) {
+                    break;
+                }
+            }
+        }
+
+        self.consume(TokenType::RightBrace, "Expect '}' after map entries.")?;
+        Ok(Expr::Map(Rc::new(MapExpr {
+            brace,
+            keys,
+            values,
+        })))
+    }
+
     fn consume(&mut self, ttype: TokenType, message: &str) -> Result<Token, LoxResult> {
         if self.check(ttype) {
             Ok(self.advance().dup())
@@ -541,6 +1227,15 @@ impl<'a> Parser<'a> {
         }
     }
 
+    // Members of classes may also be private '#names'
+    fn member_name(&mut self, message: &str) -> Result<Token, LoxResult> {
+        if self.is_match(&[TokenType::PrivateIdentifier]) {
+            Ok(self.previous().dup())
+        } else {
+            self.consume(TokenType::Identifier, message)
+        }
+    }
+
     fn error(&mut self, token: &Token, message: &str) -> LoxResult {
         self.had_error = true;
         LoxResult::parse_error(token, message)
@@ -557,10 +1252,14 @@ impl<'a> Parser<'a> {
             if matches!(
                 self.peek().token_type(),
                 TokenType::Class
+                    | TokenType::Enum
+                    | TokenType::Trait
                     | TokenType::Fun
                     | TokenType::Var
+                    | TokenType::Const
                     | TokenType::For
                     | TokenType::If
+                    | TokenType::Match
                     | TokenType::While
                     | TokenType::Print
                     | TokenType::Return
@@ -590,6 +1289,14 @@ impl<'a> Parser<'a> {
         }
     }
 
+    fn check_ahead(&self, types: &[TokenType]) -> bool {
+        types.iter().enumerate().all(|(i, &t)| {
+            self.tokens
+                .get(self.current + i)
+                .is_some_and(|token| token.is(t))
+        })
+    }
+
     fn advance(&mut self) -> &Token {
         if !self.is_at_end() {
             self.current += 1;
@@ -610,3 +1317,16 @@ impl<'a> Parser<'a> {
         self.tokens.get(self.current - 1).unwrap()
     }
 }
+
+// The call with `value` put before its other arguments
+fn piped_call(value: Rc<Expr>, call: &CallExpr) -> CallExpr {
+    let mut arguments = vec![value];
+    arguments.extend(call.arguments.iter().cloned());
+    CallExpr {
+        callee: Rc::clone(&call.callee),
+        paren: call.paren.dup(),
+        arguments,
+        names: call.names.iter().map(Token::dup).collect(),
+        named_arguments: call.named_arguments.clone(),
+    }
+}
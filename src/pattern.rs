@@ -0,0 +1,176 @@
+use crate::error::*;
+use crate::expr::*;
+use crate::object::*;
+use crate::token::*;
+use std::rc::Rc;
+
+pub enum Pattern {
+    Alternative(Rc<AlternativePattern>),
+    Binding(Rc<BindingPattern>),
+    Class(Rc<ClassPattern>),
+    List(Rc<ListPattern>),
+    Literal(Rc<LiteralPattern>),
+    Record(Rc<RecordPattern>),
+    Value(Rc<ValuePattern>),
+    Wildcard(Rc<WildcardPattern>),
+}
+
+impl PartialEq for Pattern {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Pattern::Alternative(a), Pattern::Alternative(b)) => Rc::ptr_eq(a, b),
+            (Pattern::Binding(a), Pattern::Binding(b)) => Rc::ptr_eq(a, b),
+            (Pattern::Class(a), Pattern::Class(b)) => Rc::ptr_eq(a, b),
+            (Pattern::List(a), Pattern::List(b)) => Rc::ptr_eq(a, b),
+            (Pattern::Literal(a), Pattern::Literal(b)) => Rc::ptr_eq(a, b),
+            (Pattern::Record(a), Pattern::Record(b)) => Rc::ptr_eq(a, b),
+            (Pattern::Value(a), Pattern::Value(b)) => Rc::ptr_eq(a, b),
+            (Pattern::Wildcard(a), Pattern::Wildcard(b)) => Rc::ptr_eq(a, b),
+            _ => false,
+        }
+    }
+}
+
+impl Eq for Pattern {}
+
+use std::hash::{Hash, Hasher};
+impl Hash for Pattern {
+    fn hash<H>(&self, hasher: &mut H)
+    where
+        H: Hasher,
+    {
+        match self {
+            Pattern::Alternative(a) => {
+                hasher.write_usize(Rc::as_ptr(a) as usize);
+            }
+            Pattern::Binding(a) => {
+                hasher.write_usize(Rc::as_ptr(a) as usize);
+            }
+            Pattern::Class(a) => {
+                hasher.write_usize(Rc::as_ptr(a) as usize);
+            }
+            Pattern::List(a) => {
+                hasher.write_usize(Rc::as_ptr(a) as usize);
+            }
+            Pattern::Literal(a) => {
+                hasher.write_usize(Rc::as_ptr(a) as usize);
+            }
+            Pattern::Record(a) => {
+                hasher.write_usize(Rc::as_ptr(a) as usize);
+            }
+            Pattern::Value(a) => {
+                hasher.write_usize(Rc::as_ptr(a) as usize);
+            }
+            Pattern::Wildcard(a) => {
+                hasher.write_usize(Rc::as_ptr(a) as usize);
+            }
+        }
+    }
+}
+
+impl Pattern {
+    pub fn accept<T>(
+        &self,
+        wrapper: Rc<Pattern>,
+        pattern_visitor: &dyn PatternVisitor<T>,
+    ) -> Result<T, LoxResult> {
+        match self {
+            Pattern::Alternative(v) => pattern_visitor.visit_alternative_pattern(wrapper, v),
+            Pattern::Binding(v) => pattern_visitor.visit_binding_pattern(wrapper, v),
+            Pattern::Class(v) => pattern_visitor.visit_class_pattern(wrapper, v),
+            Pattern::List(v) => pattern_visitor.visit_list_pattern(wrapper, v),
+            Pattern::Literal(v) => pattern_visitor.visit_literal_pattern(wrapper, v),
+            Pattern::Record(v) => pattern_visitor.visit_record_pattern(wrapper, v),
+            Pattern::Value(v) => pattern_visitor.visit_value_pattern(wrapper, v),
+            Pattern::Wildcard(v) => pattern_visitor.visit_wildcard_pattern(wrapper, v),
+        }
+    }
+}
+
+pub struct AlternativePattern {
+    pub bar: Token,
+    pub alternatives: Vec<Rc<Pattern>>,
+}
+
+pub struct BindingPattern {
+    pub name: Token,
+}
+
+pub struct ClassPattern {
+    pub path: Rc<Expr>,
+    pub name: Token,
+    pub arguments: Vec<Rc<Pattern>>,
+    pub fields: Vec<Token>,
+    pub field_patterns: Vec<Rc<Pattern>>,
+}
+
+pub struct ListPattern {
+    pub bracket: Token,
+    pub elements: Vec<Rc<Pattern>>,
+    pub defaults: Vec<Option<Rc<Expr>>>,
+    pub rest: Option<Rc<Pattern>>,
+}
+
+pub struct LiteralPattern {
+    pub token: Token,
+    pub value: Object,
+}
+
+pub struct RecordPattern {
+    pub brace: Token,
+    pub fields: Vec<Token>,
+    pub patterns: Vec<Rc<Pattern>>,
+    pub defaults: Vec<Option<Rc<Expr>>>,
+}
+
+pub struct ValuePattern {
+    pub path: Rc<Expr>,
+    pub name: Token,
+}
+
+pub struct WildcardPattern {
+    pub token: Token,
+}
+
+pub trait PatternVisitor<T> {
+    fn visit_alternative_pattern(
+        &self,
+        wrapper: Rc<Pattern>,
+        pattern: &AlternativePattern,
+    ) -> Result<T, LoxResult>;
+    fn visit_binding_pattern(
+        &self,
+        wrapper: Rc<Pattern>,
+        pattern: &BindingPattern,
+    ) -> Result<T, LoxResult>;
+    fn visit_class_pattern(
+        &self,
+        wrapper: Rc<Pattern>,
+        pattern: &ClassPattern,
+    ) -> Result<T, LoxResult>;
+    fn visit_list_pattern(
+        &self,
+        wrapper: Rc<Pattern>,
+        pattern: &ListPattern,
+    ) -> Result<T, LoxResult>;
+    fn visit_literal_pattern(
+        &self,
+        wrapper: Rc<Pattern>,
+        pattern: &LiteralPattern,
+    ) -> Result<T, LoxResult>;
+    fn visit_record_pattern(
+        &self,
+        wrapper: Rc<Pattern>,
+        pattern: &RecordPattern,
+    ) -> Result<T, LoxResult>;
+    fn visit_value_pattern(
+        &self,
+        wrapper: Rc<Pattern>,
+        pattern: &ValuePattern,
+    ) -> Result<T, LoxResult>;
+    fn visit_wildcard_pattern(
+        &self,
+        wrapper: Rc<Pattern>,
+        pattern: &WildcardPattern,
+    ) -> Result<T, LoxResult>;
+}
@@ -0,0 +1,468 @@
+use crate::environment::*;
+use crate::error::*;
+use crate::expr::*;
+use crate::interpreter::*;
+use crate::lox_enum::*;
+use crate::lox_list::*;
+use crate::object::*;
+use crate::pattern::*;
+use crate::token::*;
+use crate::token_type::*;
+use std::cell::{Cell, RefCell};
+use std::rc::Rc;
+
+// Tests a value against a pattern, defining the names the pattern binds in
+// `environment` as it goes. A failed match may leave some bindings behind, so
+// the environment should be thrown away when `matches` returns false.
+pub struct PatternMatcher<'a> {
+    interpreter: &'a Interpreter,
+    environment: Rc<RefCell<Environment>>,
+    subject: RefCell<Object>,
+    mode: BindingMode,
+    // Assignments wait here until the whole pattern has matched, so a value
+    // of the wrong shape leaves every variable untouched
+    assignments: RefCell<Vec<(Rc<Pattern>, Token, Object)>>,
+    // Destructuring reports why a value has the wrong shape instead of just
+    // failing, except while trying the alternatives of an `|` pattern
+    strict: Cell<bool>,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum BindingMode {
+    // A case of a match statement, which binds into its own environment
+    Match,
+    // `var [a, b] = ...`, which declares the names in the current scope
+    Declare,
+    // `[a, b] = ...`, which assigns to variables declared elsewhere
+    Assign,
+}
+
+impl<'a> PatternMatcher<'a> {
+    pub fn new(interpreter: &'a Interpreter, environment: Rc<RefCell<Environment>>) -> Self {
+        Self::with_mode(interpreter, environment, BindingMode::Match)
+    }
+
+    pub fn with_mode(
+        interpreter: &'a Interpreter,
+        environment: Rc<RefCell<Environment>>,
+        mode: BindingMode,
+    ) -> Self {
+        Self {
+            interpreter,
+            environment,
+            subject: RefCell::new(Object::Nil),
+            mode,
+            assignments: RefCell::new(Vec::new()),
+            strict: Cell::new(mode != BindingMode::Match),
+        }
+    }
+
+    // Binds the names in `pattern` to the parts of `subject`, or fails with
+    // an error describing how the value does not fit the pattern
+    pub fn destructure(
+        &self,
+        pattern: &Rc<Pattern>,
+        subject: Object,
+        equals: &Token,
+    ) -> Result<(), LoxResult> {
+        if self.matches(pattern, subject)? {
+            for (wrapper, name, value) in self.assignments.take() {
+                self.interpreter
+                    .assign_binding(&wrapper, &name, value, &self.environment)?;
+            }
+            Ok(())
+        } else {
+            Err(LoxResult::runtime_error(
+                equals,
+                "Value does not match the pattern.",
+            ))
+        }
+    }
+
+    pub fn matches(&self, pattern: &Rc<Pattern>, subject: Object) -> Result<bool, LoxResult> {
+        let previous = self.subject.replace(subject);
+        let result = pattern.accept(Rc::clone(pattern), self);
+        self.subject.replace(previous);
+        result
+    }
+
+    fn subject(&self) -> Object {
+        self.subject.borrow().clone()
+    }
+
+    fn evaluate(&self, expr: &Rc<Expr>) -> Result<Object, LoxResult> {
+        self.interpreter
+            .evaluate_in(Rc::clone(expr), Rc::clone(&self.environment))
+    }
+
+    // A path like `Shape.Circle` names an enum variant rather than a value,
+    // since variants with fields have no value of their own
+    fn enum_variant(&self, path: &Expr, name: &Token) -> Result<Option<Rc<LoxEnum>>, LoxResult> {
+        if let Expr::Get(get) = path {
+            if let Object::Enum(lox_enum) = self.evaluate(&get.object)? {
+                if !lox_enum.has_variant(&name.as_string()) {
+                    return Err(LoxResult::runtime_error(
+                        name,
+                        &format!(
+                            "Enum '{}' has no variant '{}'.",
+                            lox_enum.name(),
+                            name.as_string()
+                        ),
+                    ));
+                }
+                return Ok(Some(lox_enum));
+            }
+        }
+        Ok(None)
+    }
+
+    // Fails the match, or raises the error in strict mode
+    fn mismatch(&self, token: &Token, message: &str) -> Result<bool, LoxResult> {
+        if self.strict.get() {
+            Err(LoxResult::runtime_error(token, message))
+        } else {
+            Ok(false)
+        }
+    }
+
+    fn match_or_default(
+        &self,
+        pattern: &Rc<Pattern>,
+        value: Option<Object>,
+        default: &Option<Rc<Expr>>,
+    ) -> Result<Option<bool>, LoxResult> {
+        let value = match (value, default) {
+            (Some(value), _) => value,
+            (None, Some(default)) => self.evaluate(default)?,
+            (None, None) => return Ok(None),
+        };
+        self.matches(pattern, value).map(Some)
+    }
+
+    fn all_match(&self, patterns: &[Rc<Pattern>], values: &[Object]) -> Result<bool, LoxResult> {
+        for (pattern, value) in patterns.iter().zip(values) {
+            if !self.matches(pattern, value.clone())? {
+                return Ok(false);
+            }
+        }
+        Ok(true)
+    }
+
+    fn fields_match(
+        &self,
+        fields: &[Token],
+        patterns: &[Rc<Pattern>],
+        field: impl Fn(&str) -> Option<Object>,
+    ) -> Result<bool, LoxResult> {
+        for (name, pattern) in fields.iter().zip(patterns) {
+            let Some(value) = field(&name.as_string()) else {
+                return Ok(false);
+            };
+            if !self.matches(pattern, value)? {
+                return Ok(false);
+            }
+        }
+        Ok(true)
+    }
+}
+
+// The names a pattern binds, in the order they appear. The alternatives of an
+// `|` pattern all bind the same names, so only the first is searched.
+pub fn pattern_bindings(pattern: &Pattern) -> Vec<Token> {
+    match pattern {
+        Pattern::Alternative(p) => p
+            .alternatives
+            .first()
+            .map(|a| pattern_bindings(a))
+            .unwrap_or_default(),
+        Pattern::Binding(p) => vec![p.name.dup()],
+        Pattern::Class(p) => p
+            .arguments
+            .iter()
+            .chain(p.field_patterns.iter())
+            .flat_map(|a| pattern_bindings(a))
+            .collect(),
+        Pattern::List(p) => p
+            .elements
+            .iter()
+            .chain(p.rest.iter())
+            .flat_map(|e| pattern_bindings(e))
+            .collect(),
+        Pattern::Record(p) => p
+            .patterns
+            .iter()
+            .flat_map(|f| pattern_bindings(f))
+            .collect(),
+        Pattern::Literal(_) | Pattern::Value(_) | Pattern::Wildcard(_) => Vec::new(),
+    }
+}
+
+// A record field is written as a name or, for keys that are not names, a string
+fn field_name(field: &Token) -> String {
+    match (field.token_type(), &field.literal) {
+        (TokenType::String, Some(literal)) => literal.to_string(),
+        _ => field.as_string(),
+    }
+}
+
+fn record_field(subject: &Object, name: &str) -> Option<Object> {
+    match subject {
+        Object::Map(map) => map.get(&Object::Str(name.to_string())),
+        Object::Instance(instance) => instance.field(name),
+        _ => None,
+    }
+}
+
+fn check_arity(name: &Token, expected: usize, found: usize) -> Result<(), LoxResult> {
+    if expected == found {
+        Ok(())
+    } else {
+        Err(LoxResult::runtime_error(
+            name,
+            &format!(
+                "Pattern for '{}' expects {expected} fields but got {found}.",
+                name.as_string()
+            ),
+        ))
+    }
+}
+
+impl PatternVisitor<bool> for PatternMatcher<'_> {
+    fn visit_alternative_pattern(
+        &self,
+        _: Rc<Pattern>,
+        pattern: &AlternativePattern,
+    ) -> Result<bool, LoxResult> {
+        let strict = self.strict.replace(false);
+        let mut result = Ok(false);
+        for alternative in &pattern.alternatives {
+            result = self.matches(alternative, self.subject());
+            if !matches!(result, Ok(false)) {
+                break;
+            }
+        }
+        self.strict.set(strict);
+        result
+    }
+
+    fn visit_binding_pattern(
+        &self,
+        wrapper: Rc<Pattern>,
+        pattern: &BindingPattern,
+    ) -> Result<bool, LoxResult> {
+        match self.mode {
+            BindingMode::Match => self
+                .environment
+                .borrow_mut()
+                .define(&pattern.name.as_string(), self.subject()),
+            BindingMode::Declare => self
+                .environment
+                .borrow_mut()
+                .declare(&pattern.name, self.subject())?,
+            BindingMode::Assign => {
+                self.assignments
+                    .borrow_mut()
+                    .push((wrapper, pattern.name.dup(), self.subject()))
+            }
+        }
+        Ok(true)
+    }
+
+    fn visit_class_pattern(
+        &self,
+        _: Rc<Pattern>,
+        pattern: &ClassPattern,
+    ) -> Result<bool, LoxResult> {
+        if let Some(lox_enum) = self.enum_variant(&pattern.path, &pattern.name)? {
+            let variant = pattern.name.as_string();
+            return match self.subject() {
+                Object::EnumValue(value)
+                    if Rc::ptr_eq(value.owner(), &lox_enum) && value.name() == variant =>
+                {
+                    if !pattern.arguments.is_empty() {
+                        check_arity(
+                            &pattern.name,
+                            value.payload().len(),
+                            pattern.arguments.len(),
+                        )?;
+                    }
+                    Ok(self.all_match(&pattern.arguments, value.payload())?
+                        && self.fields_match(&pattern.fields, &pattern.field_patterns, |f| {
+                            value.field(f)
+                        })?)
+                }
+                _ => Ok(false),
+            };
+        }
+
+        let Object::Class(klass) = self.evaluate(&pattern.path)? else {
+            return Err(LoxResult::runtime_error(
+                &pattern.name,
+                &format!(
+                    "'{}' in a pattern must be a class or an enum variant.",
+                    pattern.name.as_string()
+                ),
+            ));
+        };
+
+        let Object::Instance(instance) = self.subject() else {
+            return Ok(false);
+        };
+        if !instance.class().inherits_from(&klass) {
+            return Ok(false);
+        }
+
+        if !pattern.arguments.is_empty() {
+            let Some(fields) = klass.fields() else {
+                return Err(LoxResult::runtime_error(
+                    &pattern.name,
+                    &format!(
+                        "'{}' is not a data class, so its fields must be matched by name.",
+                        klass.name()
+                    ),
+                ));
+            };
+            check_arity(&pattern.name, fields.len(), pattern.arguments.len())?;
+            let values = fields
+                .iter()
+                .map(|f| instance.field(f).unwrap_or(Object::Nil))
+                .collect::<Vec<Object>>();
+            if !self.all_match(&pattern.arguments, &values)? {
+                return Ok(false);
+            }
+        }
+
+        self.fields_match(&pattern.fields, &pattern.field_patterns, |f| {
+            instance.field(f)
+        })
+    }
+
+    fn visit_list_pattern(&self, _: Rc<Pattern>, pattern: &ListPattern) -> Result<bool, LoxResult> {
+        let Object::List(list) = self.subject() else {
+            return self.mismatch(
+                &pattern.bracket,
+                &format!(
+                    "Expected a list to destructure but got a value of type '{}'.",
+                    self.subject().type_name()
+                ),
+            );
+        };
+        let values = list.borrow().clone();
+
+        let required = pattern.defaults.iter().filter(|d| d.is_none()).count();
+        let most = pattern.elements.len();
+        let found = values.len();
+        let expected = if pattern.rest.is_some() {
+            (found < required).then(|| format!("at least {required}"))
+        } else if required == most {
+            (found != most).then(|| most.to_string())
+        } else {
+            (found < required || found > most).then(|| format!("{required} to {most}"))
+        };
+        if let Some(expected) = expected {
+            return self.mismatch(
+                &pattern.bracket,
+                &format!(
+                    "Expected {expected} element{} but got {found}.",
+                    if expected == "1" { "" } else { "s" }
+                ),
+            );
+        }
+
+        let elements = pattern.elements.iter().zip(&pattern.defaults);
+        for (i, (element, default)) in elements.enumerate() {
+            if self.match_or_default(element, values.get(i).cloned(), default)? != Some(true) {
+                return Ok(false);
+            }
+        }
+
+        if let Some(rest) = &pattern.rest {
+            let rest_values = values.into_iter().skip(most).collect::<Vec<Object>>();
+            return self.matches(rest, Object::List(Rc::new(LoxList::new(rest_values))));
+        }
+        Ok(true)
+    }
+
+    fn visit_literal_pattern(
+        &self,
+        _: Rc<Pattern>,
+        pattern: &LiteralPattern,
+    ) -> Result<bool, LoxResult> {
+        self.interpreter
+            .values_equal(&self.subject(), &pattern.value, &pattern.token)
+    }
+
+    fn visit_record_pattern(
+        &self,
+        _: Rc<Pattern>,
+        pattern: &RecordPattern,
+    ) -> Result<bool, LoxResult> {
+        let subject = self.subject();
+        if !matches!(subject, Object::Map(_) | Object::Instance(_)) {
+            return self.mismatch(
+                &pattern.brace,
+                &format!(
+                    "Expected a map or an instance to destructure but got a value of type '{}'.",
+                    subject.type_name()
+                ),
+            );
+        }
+
+        let fields = pattern
+            .fields
+            .iter()
+            .zip(&pattern.patterns)
+            .zip(&pattern.defaults);
+        for ((name, field_pattern), default) in fields {
+            match self.match_or_default(
+                field_pattern,
+                record_field(&subject, &field_name(name)),
+                default,
+            )? {
+                Some(true) => {}
+                Some(false) => return Ok(false),
+                None if matches!(subject, Object::Map(_)) => {
+                    return self.mismatch(name, &format!("Map has no key '{}'.", field_name(name)))
+                }
+                None => {
+                    return self.mismatch(
+                        name,
+                        &format!(
+                            "Instance of '{}' has no field '{}'.",
+                            subject.type_name(),
+                            field_name(name)
+                        ),
+                    )
+                }
+            }
+        }
+        Ok(true)
+    }
+
+    fn visit_value_pattern(
+        &self,
+        _: Rc<Pattern>,
+        pattern: &ValuePattern,
+    ) -> Result<bool, LoxResult> {
+        if let Some(lox_enum) = self.enum_variant(&pattern.path, &pattern.name)? {
+            return Ok(match self.subject() {
+                Object::EnumValue(value) => {
+                    Rc::ptr_eq(value.owner(), &lox_enum) && value.name() == pattern.name.as_string()
+                }
+                _ => false,
+            });
+        }
+
+        let value = self.evaluate(&pattern.path)?;
+        self.interpreter
+            .values_equal(&self.subject(), &value, &pattern.name)
+    }
+
+    fn visit_wildcard_pattern(
+        &self,
+        _: Rc<Pattern>,
+        _: &WildcardPattern,
+    ) -> Result<bool, LoxResult> {
+        Ok(true)
+    }
+}
@@ -1,20 +1,48 @@
 use crate::error::*;
 use crate::expr::*;
 use crate::interpreter::*;
+use crate::lox_enum::*;
+use crate::pattern::*;
+use crate::pattern_matcher::*;
 use crate::stmt::*;
 use crate::token::*;
+use crate::token_type::*;
 use std::cell::RefCell;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::ops::Deref;
 use std::rc::Rc;
 
 pub struct Resolver<'a> {
     interpreter: &'a Interpreter,
     scopes: RefCell<Vec<RefCell<HashMap<String, bool>>>>,
+    // Names declared with `const`, one set per scope plus one for globals
+    constants: RefCell<Vec<HashSet<String>>>,
     had_error: RefCell<bool>,
     current_function: RefCell<FunctionType>,
     current_class: RefCell<ClassType>,
     in_while: RefCell<bool>,
+    in_generator: RefCell<bool>,
+    classes: RefCell<HashMap<String, ClassInfo>>,
+    enums: RefCell<HashMap<String, Vec<String>>>,
+    private_scopes: RefCell<Vec<PrivateScope>>,
+    // Whether the bindings of the pattern being resolved assign to existing
+    // variables rather than declaring new ones
+    assigning: RefCell<bool>,
+}
+
+// Private members seen while resolving the body of a class or trait
+struct PrivateScope {
+    owner: String,
+    key: String,
+    declared: HashSet<String>,
+    used: Vec<Token>,
+}
+
+// What the resolver knows about classes and traits declared in the same source
+struct ClassInfo {
+    superclass: Option<String>,
+    methods: Vec<String>,
+    abstract_methods: Vec<String>,
 }
 
 #[derive(PartialEq)]
@@ -30,6 +58,7 @@ enum ClassType {
     None,
     Class,
     SubClass,
+    Trait,
 }
 
 impl<'a> Resolver<'a> {
@@ -37,10 +66,16 @@ impl<'a> Resolver<'a> {
         Self {
             interpreter,
             scopes: RefCell::new(Vec::new()),
+            constants: RefCell::new(vec![HashSet::new()]),
             had_error: RefCell::new(false),
             current_function: RefCell::new(FunctionType::None),
             current_class: RefCell::new(ClassType::None),
             in_while: RefCell::new(false),
+            in_generator: RefCell::new(false),
+            classes: RefCell::new(HashMap::new()),
+            enums: RefCell::new(HashMap::new()),
+            private_scopes: RefCell::new(Vec::new()),
+            assigning: RefCell::new(false),
         }
     }
 
@@ -60,15 +95,29 @@ impl<'a> Resolver<'a> {
         expr.accept(expr.clone(), self)
     }
 
+    fn resolve_pattern(&self, pattern: &Rc<Pattern>) -> Result<(), LoxResult> {
+        pattern.accept(Rc::clone(pattern), self)
+    }
+
     fn begin_scope(&self) {
         self.scopes.borrow_mut().push(RefCell::new(HashMap::new()));
+        self.constants.borrow_mut().push(HashSet::new());
     }
 
     fn end_scope(&self) {
         self.scopes.borrow_mut().pop();
+        self.constants.borrow_mut().pop();
     }
 
     fn declare(&self, name: &Token) {
+        if self.scopes.borrow().is_empty() && self.constants.borrow()[0].contains(&name.as_string())
+        {
+            self.error(
+                name,
+                &format!("Can't redefine constant '{}'.", name.as_string()),
+            );
+        }
+
         if let Some(scope) = self.scopes.borrow().last() {
             if scope.borrow().contains_key(&name.as_string()) {
                 self.error(name, "Already a variable with this name in this scope.");
@@ -84,13 +133,47 @@ impl<'a> Resolver<'a> {
         }
     }
 
+    fn make_constant(&self, name: &Token) {
+        if let Some(constants) = self.constants.borrow_mut().last_mut() {
+            constants.insert(name.as_string());
+        }
+    }
+
+    // Looks in the scope the name resolves to, or among the globals if it
+    // isn't a local
+    fn is_constant(&self, name: &Token) -> bool {
+        let name = name.as_string();
+        let scopes = self.scopes.borrow();
+        let depth = scopes
+            .iter()
+            .rposition(|scope| scope.borrow().contains_key(&name))
+            .map_or(0, |i| i + 1);
+        self.constants.borrow()[depth].contains(&name)
+    }
+
     fn resolve_local(&self, expr: Rc<Expr>, name: &Token) {
-        for (scope, map) in self.scopes.borrow().iter().rev().enumerate() {
-            if map.borrow().contains_key(&name.as_string()) {
-                self.interpreter.resolve(expr, scope);
-                return;
-            }
+        if let Some(depth) = self.local_depth(name) {
+            self.interpreter.resolve(expr, depth);
+        }
+    }
+
+    fn local_depth(&self, name: &Token) -> Option<usize> {
+        self.scopes
+            .borrow()
+            .iter()
+            .rev()
+            .position(|scope| scope.borrow().contains_key(&name.as_string()))
+    }
+
+    fn resolve_pattern_default(
+        &self,
+        pattern: &Rc<Pattern>,
+        default: &Option<Rc<Expr>>,
+    ) -> Result<(), LoxResult> {
+        if let Some(default) = default {
+            self.resolve_expr(default.clone())?;
         }
+        self.resolve_pattern(pattern)
     }
 
     fn resolve_function(
@@ -98,20 +181,271 @@ impl<'a> Resolver<'a> {
         function: &FunctionStmt,
         ftype: FunctionType,
     ) -> Result<(), LoxResult> {
+        if function.is_generator && ftype == FunctionType::Initializer {
+            self.error(&function.name, "An initializer can't be a generator.");
+        }
+
         let enclosing_function = self.current_function.replace(ftype);
+        let enclosing_generator = self.in_generator.replace(function.is_generator);
+        let enclosing_loop = self.in_while.replace(false);
         self.begin_scope();
 
-        for param in function.params.iter() {
+        // A default value can refer to the parameters before it
+        for (param, default) in function.params.iter().zip(function.defaults.iter()) {
+            if let Some(default) = default {
+                self.resolve_expr(default.clone())?;
+            }
             self.declare(param);
             self.define(param);
         }
+        if let Some(rest) = &function.rest {
+            self.declare(rest);
+            self.define(rest);
+        }
 
         self.resolve(&function.body)?;
         self.end_scope();
+        self.in_while.replace(enclosing_loop);
+        self.in_generator.replace(enclosing_generator);
         self.current_function.replace(enclosing_function);
         Ok(())
     }
 
+    fn resolve_methods(
+        &self,
+        declaration: Rc<Stmt>,
+        owner: &Token,
+        methods: &Rc<Vec<Rc<Stmt>>>,
+    ) -> Result<(), LoxResult> {
+        let mut declared = HashSet::new();
+        for method in methods.iter() {
+            if let Stmt::Function(f) = method.deref() {
+                if f.name.is_private() {
+                    declared.insert(f.name.as_string());
+                }
+            }
+        }
+        self.private_scopes.borrow_mut().push(PrivateScope {
+            owner: owner.as_string(),
+            key: self.interpreter.declare_owner(declaration, owner),
+            declared,
+            used: Vec::new(),
+        });
+
+        self.begin_scope();
+        self.scopes
+            .borrow()
+            .last()
+            .unwrap()
+            .borrow_mut()
+            .insert("this".to_string(), true);
+
+        for method in methods.deref() {
+            if let Stmt::Function(method) = method.deref() {
+                let declaration = if method.name.as_string() == "init" {
+                    FunctionType::Initializer
+                } else {
+                    FunctionType::Method
+                };
+                self.resolve_function(method, declaration)?;
+            } else {
+                return Err(LoxResult::runtime_error(
+                    owner,
+                    "Class method did not resolve into a function statement",
+                ));
+            }
+        }
+
+        self.end_scope();
+
+        // Fields are declared by assigning them anywhere in the class, so the
+        // check can only happen once every method has been seen
+        let scope = self.private_scopes.borrow_mut().pop().unwrap();
+        for name in scope.used.iter() {
+            if !scope.declared.contains(&name.as_string()) {
+                self.error(
+                    name,
+                    &format!(
+                        "Private member '{}' is not declared in '{}'.",
+                        name.as_string(),
+                        scope.owner
+                    ),
+                );
+            }
+        }
+        Ok(())
+    }
+
+    // Private members are only reachable as `this.#name` inside the class that declares them
+    fn resolve_private(&self, wrapper: Rc<Expr>, object: &Expr, name: &Token, is_assignment: bool) {
+        let mut scopes = self.private_scopes.borrow_mut();
+        let Some(scope) = scopes.last_mut() else {
+            self.error(name, "Can't access a private member outside of a class.");
+            return;
+        };
+
+        if !matches!(object, Expr::This(_)) {
+            self.error(name, "Private members can only be accessed through 'this'.");
+            return;
+        }
+
+        if is_assignment {
+            scope.declared.insert(name.as_string());
+        } else {
+            scope.used.push(name.dup());
+        }
+        self.interpreter.resolve_private(wrapper, &scope.key);
+    }
+
+    fn class_info(
+        &self,
+        methods: &Rc<Vec<Rc<Stmt>>>,
+        abstract_methods: &Rc<Vec<Token>>,
+        superclass: Option<String>,
+    ) -> ClassInfo {
+        let methods = methods
+            .iter()
+            .filter_map(|m| match m.deref() {
+                Stmt::Function(f) => Some(f.name.as_string()),
+                _ => None,
+            })
+            .collect::<Vec<String>>();
+
+        for name in abstract_methods.iter() {
+            if methods.contains(&name.as_string()) {
+                self.error(name, "Method can't be both abstract and implemented.");
+            }
+        }
+
+        ClassInfo {
+            superclass,
+            methods,
+            abstract_methods: abstract_methods.iter().map(|m| m.as_string()).collect(),
+        }
+    }
+
+    // The initializer of a data class is generated from its fields
+    fn check_data_class(&self, stmt: &ClassStmt, fields: &[Token]) {
+        if let Some(Expr::Variable(v)) = stmt.superclass.as_deref() {
+            self.error(&v.name, "A data class can't inherit from another class.");
+        }
+
+        let mut names = HashSet::new();
+        for field in fields {
+            if !names.insert(field.as_string()) {
+                self.error(field, "Already a field with this name in this data class.");
+            }
+        }
+
+        for method in stmt.methods.iter() {
+            if let Stmt::Function(func) = method.deref() {
+                if func.name.as_string() == "init" {
+                    self.error(&func.name, "A data class can't define 'init'.");
+                }
+            }
+        }
+    }
+
+    // The methods implemented by `superclass` and the classes above it
+    fn inherited_methods(&self, superclass: &Option<String>) -> Vec<String> {
+        let classes = self.classes.borrow();
+        let mut methods = Vec::new();
+        let mut superclass = superclass.clone();
+        while let Some(parent) = superclass.and_then(|name| classes.get(&name)) {
+            methods.extend_from_slice(&parent.methods);
+            superclass = parent.superclass.clone();
+        }
+        methods
+    }
+
+    // A class with no abstract methods of its own or left over from its traits
+    // is concrete, so it should implement everything it inherits as abstract
+    fn check_abstract_methods(&self, name: &Token, info: &ClassInfo) {
+        if !info.abstract_methods.is_empty() {
+            return;
+        }
+
+        let classes = self.classes.borrow();
+        let mut implemented = info.methods.clone();
+        let mut superclass = info.superclass.clone();
+        let mut missing: Vec<(String, String)> = Vec::new();
+
+        while let Some(super_name) = superclass {
+            let Some(parent) = classes.get(&super_name) else {
+                break;
+            };
+            for method in &parent.abstract_methods {
+                if !implemented.contains(method) && !missing.iter().any(|(m, _)| m == method) {
+                    missing.push((method.clone(), super_name.clone()));
+                }
+            }
+            implemented.extend_from_slice(&parent.methods);
+            superclass = parent.superclass.clone();
+        }
+
+        for (method, owner) in missing {
+            if !implemented.contains(&method) {
+                self.warning(
+                    name,
+                    &format!(
+                        "Class '{}' does not implement abstract method '{method}' inherited from '{owner}'.",
+                        name.as_string()
+                    ),
+                );
+            }
+        }
+    }
+
+    // When every case names a variant of the same enum, warn about the
+    // variants that no unguarded case covers
+    fn check_enum_coverage(&self, stmt: &MatchStmt) {
+        let mut enum_name = None;
+        let mut covered = HashSet::new();
+        for (pattern, guard) in stmt.patterns.iter().zip(stmt.guards.iter()) {
+            let mut variants = Vec::new();
+            if !enum_variants(pattern, &mut variants) {
+                return;
+            }
+            for (name, variant, complete) in variants {
+                if enum_name.get_or_insert_with(|| name.clone()) != &name {
+                    return;
+                }
+                if guard.is_none() && complete {
+                    covered.insert(variant);
+                }
+            }
+        }
+
+        let Some(name) = enum_name else {
+            return;
+        };
+        if let Some(variants) = self.enums.borrow().get(&name) {
+            let missing = variants
+                .iter()
+                .filter(|v| !covered.contains(*v))
+                .cloned()
+                .collect::<Vec<String>>();
+            if !missing.is_empty() {
+                self.warning(
+                    &stmt.keyword,
+                    &format!(
+                        "Match on enum '{name}' does not cover: {}.",
+                        missing.join(", ")
+                    ),
+                );
+            }
+        }
+    }
+
+    fn warning(&self, token: &Token, message: &str) {
+        eprintln!(
+            "line {} at '{}' Warning: {}",
+            token.line,
+            token.as_string(),
+            message
+        );
+    }
+
     fn error(&self, token: &Token, message: &str) {
         self.had_error.replace(true);
         LoxResult::runtime_error(token, message);
@@ -123,7 +457,7 @@ impl<'a> Resolver<'a> {
 }
 
 impl<'a> StmtVisitor<()> for Resolver<'a> {
-    fn visit_class_stmt(&self, _: Rc<Stmt>, stmt: &ClassStmt) -> Result<(), LoxResult> {
+    fn visit_class_stmt(&self, wrapper: Rc<Stmt>, stmt: &ClassStmt) -> Result<(), LoxResult> {
         let enclosing_class = self.current_class.replace(ClassType::Class);
         self.declare(&stmt.name);
         self.define(&stmt.name);
@@ -136,6 +470,18 @@ impl<'a> StmtVisitor<()> for Resolver<'a> {
                 }
             }
             self.resolve_expr(superclass.clone())?;
+        }
+
+        for t in stmt.traits.iter() {
+            if let Expr::Variable(v) = t.deref() {
+                if stmt.name.as_string() == v.name.as_string() {
+                    self.error(&v.name, "A class can't use itself as a trait.");
+                }
+            }
+            self.resolve_expr(t.clone())?;
+        }
+
+        if stmt.superclass.is_some() {
             self.begin_scope();
             self.scopes
                 .borrow()
@@ -145,31 +491,11 @@ impl<'a> StmtVisitor<()> for Resolver<'a> {
                 .insert("super".to_string(), true);
         }
 
-        self.begin_scope();
-        self.scopes
-            .borrow()
-            .last()
-            .unwrap()
-            .borrow_mut()
-            .insert("this".to_string(), true);
-
-        for method in stmt.methods.deref() {
-            if let Stmt::Function(method) = method.deref() {
-                let declaration = if method.name.as_string() == "init" {
-                    FunctionType::Initializer
-                } else {
-                    FunctionType::Method
-                };
-                self.resolve_function(method, declaration)?;
-            } else {
-                return Err(LoxResult::runtime_error(
-                    &stmt.name,
-                    "Class method did not resolve into a function statement",
-                ));
-            }
+        if let Some(fields) = &stmt.fields {
+            self.check_data_class(stmt, fields);
         }
 
-        self.end_scope();
+        self.resolve_methods(wrapper, &stmt.name, &stmt.methods)?;
 
         if stmt.superclass.is_some() {
             self.end_scope();
@@ -177,6 +503,117 @@ impl<'a> StmtVisitor<()> for Resolver<'a> {
 
         self.current_class.replace(enclosing_class);
 
+        let superclass = stmt.superclass.as_ref().and_then(|s| match s.deref() {
+            Expr::Variable(v) => Some(v.name.as_string()),
+            _ => None,
+        });
+        let mut info = self.class_info(&stmt.methods, &stmt.abstract_methods, superclass);
+        let mut trait_abstracts = Vec::new();
+        for t in stmt.traits.iter() {
+            if let Expr::Variable(v) = t.deref() {
+                if let Some(t) = self.classes.borrow().get(&v.name.as_string()) {
+                    info.methods.extend_from_slice(&t.methods);
+                    trait_abstracts.extend_from_slice(&t.abstract_methods);
+                }
+            }
+        }
+        // A trait's abstract method only keeps the class abstract if nothing
+        // in the class, its other traits or its superclasses implements it
+        let inherited = self.inherited_methods(&info.superclass);
+        for method in trait_abstracts {
+            if !info.methods.contains(&method)
+                && !inherited.contains(&method)
+                && !info.abstract_methods.contains(&method)
+            {
+                info.abstract_methods.push(method);
+            }
+        }
+        self.check_abstract_methods(&stmt.name, &info);
+        self.classes
+            .borrow_mut()
+            .insert(stmt.name.as_string(), info);
+
+        Ok(())
+    }
+
+    fn visit_trait_stmt(&self, wrapper: Rc<Stmt>, stmt: &TraitStmt) -> Result<(), LoxResult> {
+        let enclosing_class = self.current_class.replace(ClassType::Trait);
+        self.declare(&stmt.name);
+        self.define(&stmt.name);
+
+        self.resolve_methods(wrapper, &stmt.name, &stmt.methods)?;
+
+        self.current_class.replace(enclosing_class);
+
+        let info = self.class_info(&stmt.methods, &stmt.abstract_methods, None);
+        self.classes
+            .borrow_mut()
+            .insert(stmt.name.as_string(), info);
+        Ok(())
+    }
+
+    fn visit_enum_stmt(&self, _: Rc<Stmt>, stmt: &EnumStmt) -> Result<(), LoxResult> {
+        self.declare(&stmt.name);
+        self.define(&stmt.name);
+
+        let mut variants = HashSet::new();
+        for (variant, payload) in stmt.variants.iter().zip(stmt.payloads.iter()) {
+            let name = variant.as_string();
+            if ENUM_METHODS.contains(&name.as_str()) {
+                self.error(variant, &format!("Can't use '{name}' as a variant name."));
+            } else if !variants.insert(name) {
+                self.error(variant, "Already a variant with this name in this enum.");
+            }
+
+            let mut fields = HashSet::new();
+            for field in payload.iter().flatten() {
+                let name = field.as_string();
+                if name == "name" || name == "ordinal" {
+                    self.error(
+                        field,
+                        &format!("Can't use '{name}' as a variant field name."),
+                    );
+                } else if !fields.insert(name) {
+                    self.error(field, "Already a field with this name in this variant.");
+                }
+            }
+        }
+
+        self.enums.borrow_mut().insert(
+            stmt.name.as_string(),
+            stmt.variants.iter().map(|v| v.as_string()).collect(),
+        );
+        Ok(())
+    }
+
+    fn visit_match_stmt(&self, _: Rc<Stmt>, stmt: &MatchStmt) -> Result<(), LoxResult> {
+        self.resolve_expr(stmt.subject.clone())?;
+
+        let mut catch_all = false;
+        let cases = stmt
+            .patterns
+            .iter()
+            .zip(stmt.guards.iter())
+            .zip(stmt.bodies.iter());
+        for ((pattern, guard), body) in cases {
+            if catch_all {
+                self.warning(
+                    pattern_token(pattern),
+                    "Unreachable case after a catch-all case.",
+                );
+            }
+            catch_all |= guard.is_none() && is_irrefutable(pattern);
+
+            self.begin_scope();
+            self.resolve_pattern(pattern)?;
+            if let Some(guard) = guard {
+                self.resolve_expr(guard.clone())?;
+            }
+            self.resolve_stmt(body.clone())?;
+            self.end_scope();
+        }
+
+        self.check_enum_coverage(stmt);
         Ok(())
     }
 
@@ -189,6 +626,9 @@ impl<'a> StmtVisitor<()> for Resolver<'a> {
             if *self.current_function.borrow() == FunctionType::Initializer {
                 self.error(&stmt.keyword, "Can't return a value from an initializer");
             }
+            if *self.in_generator.borrow() {
+                self.error(&stmt.keyword, "Can't return a value from a generator");
+            }
             self.resolve_expr(value)?;
         }
 
@@ -218,6 +658,19 @@ impl<'a> StmtVisitor<()> for Resolver<'a> {
         Ok(())
     }
 
+    fn visit_forin_stmt(&self, _: Rc<Stmt>, stmt: &ForInStmt) -> Result<(), LoxResult> {
+        self.resolve_expr(stmt.iterable.clone())?;
+
+        let previous_nesting = self.in_while.replace(true);
+        self.begin_scope();
+        self.declare(&stmt.name);
+        self.define(&stmt.name);
+        self.resolve_stmt(stmt.body.clone())?;
+        self.end_scope();
+        self.in_while.replace(previous_nesting);
+        Ok(())
+    }
+
     fn visit_if_stmt(&self, _: Rc<Stmt>, stmt: &IfStmt) -> Result<(), LoxResult> {
         self.resolve_expr(stmt.condition.clone())?;
         self.resolve_stmt(stmt.then_branch.clone())?;
@@ -244,12 +697,59 @@ impl<'a> StmtVisitor<()> for Resolver<'a> {
         Ok(())
     }
 
+    fn visit_yield_stmt(&self, _: Rc<Stmt>, stmt: &YieldStmt) -> Result<(), LoxResult> {
+        if !*self.in_generator.borrow() {
+            self.error(&stmt.keyword, "Can't yield outside of a generator function");
+        }
+
+        if let Some(value) = stmt.value.clone() {
+            self.resolve_expr(value)?;
+        }
+        Ok(())
+    }
+
+    fn visit_destructure_stmt(&self, _: Rc<Stmt>, stmt: &DestructureStmt) -> Result<(), LoxResult> {
+        self.resolve_expr(stmt.value.clone())?;
+
+        let bindings = pattern_bindings(&stmt.pattern);
+        for (i, name) in bindings.iter().enumerate() {
+            if bindings[..i]
+                .iter()
+                .any(|n| n.as_string() == name.as_string())
+            {
+                self.error(
+                    name,
+                    &format!("'{}' is bound more than once.", name.as_string()),
+                );
+            }
+        }
+
+        let assigning = self.assigning.replace(stmt.keyword.is_none());
+        let result = self.resolve_pattern(&stmt.pattern);
+        self.assigning.replace(assigning);
+        result?;
+
+        if stmt
+            .keyword
+            .as_ref()
+            .is_some_and(|k| k.is(TokenType::Const))
+        {
+            for name in &bindings {
+                self.make_constant(name);
+            }
+        }
+        Ok(())
+    }
+
     fn visit_var_stmt(&self, _: Rc<Stmt>, stmt: &VarStmt) -> Result<(), LoxResult> {
         self.declare(&stmt.name);
         if let Some(init) = stmt.initializer.clone() {
             self.resolve_expr(init)?;
         }
         self.define(&stmt.name);
+        if stmt.is_const {
+            self.make_constant(&stmt.name);
+        }
         Ok(())
     }
 }
@@ -262,6 +762,7 @@ impl<'a> ExprVisitor<()> for Resolver<'a> {
                 &expr.keyword,
                 "Can't use 'super' in a class with no superclass.",
             ),
+            ClassType::Trait => self.error(&expr.keyword, "Can't use 'super' in a trait."),
             ClassType::SubClass => {}
         }
 
@@ -278,14 +779,62 @@ impl<'a> ExprVisitor<()> for Resolver<'a> {
         Ok(())
     }
 
-    fn visit_set_expr(&self, _wrapper: Rc<Expr>, expr: &SetExpr) -> Result<(), LoxResult> {
+    fn visit_set_expr(&self, wrapper: Rc<Expr>, expr: &SetExpr) -> Result<(), LoxResult> {
         self.resolve_expr(expr.value.clone())?;
         self.resolve_expr(expr.object.clone())?;
+        if expr.name.is_private() {
+            self.resolve_private(wrapper, &expr.object, &expr.name, true);
+        }
+        Ok(())
+    }
+
+    fn visit_get_expr(&self, wrapper: Rc<Expr>, expr: &GetExpr) -> Result<(), LoxResult> {
+        self.resolve_expr(expr.object.clone())?;
+        if expr.name.is_private() {
+            self.resolve_private(wrapper, &expr.object, &expr.name, false);
+        }
         Ok(())
     }
 
-    fn visit_get_expr(&self, _: Rc<Expr>, expr: &GetExpr) -> Result<(), LoxResult> {
+    fn visit_optionalget_expr(
+        &self,
+        wrapper: Rc<Expr>,
+        expr: &OptionalGetExpr,
+    ) -> Result<(), LoxResult> {
         self.resolve_expr(expr.object.clone())?;
+        if expr.name.is_private() {
+            self.resolve_private(wrapper, &expr.object, &expr.name, false);
+        }
+        Ok(())
+    }
+
+    fn visit_optionalchain_expr(
+        &self,
+        _: Rc<Expr>,
+        expr: &OptionalChainExpr,
+    ) -> Result<(), LoxResult> {
+        self.resolve_expr(expr.chain.clone())
+    }
+
+    fn visit_index_expr(&self, _: Rc<Expr>, expr: &IndexExpr) -> Result<(), LoxResult> {
+        self.resolve_expr(expr.object.clone())?;
+        self.resolve_expr(expr.index.clone())?;
+        Ok(())
+    }
+
+    fn visit_range_expr(&self, _: Rc<Expr>, expr: &RangeExpr) -> Result<(), LoxResult> {
+        self.resolve_expr(expr.start.clone())?;
+        self.resolve_expr(expr.end.clone())?;
+        if let Some(step) = &expr.step {
+            self.resolve_expr(step.clone())?;
+        }
+        Ok(())
+    }
+
+    fn visit_setindex_expr(&self, _: Rc<Expr>, expr: &SetIndexExpr) -> Result<(), LoxResult> {
+        self.resolve_expr(expr.value.clone())?;
+        self.resolve_expr(expr.object.clone())?;
+        self.resolve_expr(expr.index.clone())?;
         Ok(())
     }
 
@@ -295,6 +844,14 @@ impl<'a> ExprVisitor<()> for Resolver<'a> {
         for argument in expr.arguments.iter() {
             self.resolve_expr(argument.clone())?;
         }
+
+        let mut names = HashSet::new();
+        for (name, argument) in expr.names.iter().zip(expr.named_arguments.iter()) {
+            if !names.insert(name.as_string()) {
+                self.error(name, "Argument is given more than once.");
+            }
+            self.resolve_expr(argument.clone())?;
+        }
         Ok(())
     }
 
@@ -305,6 +862,12 @@ impl<'a> ExprVisitor<()> for Resolver<'a> {
     }
 
     fn visit_assign_expr(&self, wrapper: Rc<Expr>, expr: &AssignExpr) -> Result<(), LoxResult> {
+        if self.is_constant(&expr.name) {
+            self.error(
+                &expr.name,
+                &format!("Can't assign to constant '{}'.", expr.name.as_string()),
+            );
+        }
         self.resolve_expr(expr.value.clone())?;
         self.resolve_local(wrapper, &expr.name);
         Ok(())
@@ -314,6 +877,21 @@ impl<'a> ExprVisitor<()> for Resolver<'a> {
         Ok(())
     }
 
+    fn visit_list_expr(&self, _: Rc<Expr>, expr: &ListExpr) -> Result<(), LoxResult> {
+        for element in expr.elements.iter() {
+            self.resolve_expr(element.clone())?;
+        }
+        Ok(())
+    }
+
+    fn visit_map_expr(&self, _: Rc<Expr>, expr: &MapExpr) -> Result<(), LoxResult> {
+        for (key, value) in expr.keys.iter().zip(expr.values.iter()) {
+            self.resolve_expr(key.clone())?;
+            self.resolve_expr(value.clone())?;
+        }
+        Ok(())
+    }
+
     fn visit_grouping_expr(&self, _: Rc<Expr>, expr: &GroupingExpr) -> Result<(), LoxResult> {
         self.resolve_expr(expr.expression.clone())?;
         Ok(())
@@ -351,3 +929,157 @@ impl<'a> ExprVisitor<()> for Resolver<'a> {
         }
     }
 }
+
+impl PatternVisitor<()> for Resolver<'_> {
+    fn visit_alternative_pattern(
+        &self,
+        _: Rc<Pattern>,
+        pattern: &AlternativePattern,
+    ) -> Result<(), LoxResult> {
+        // Every alternative binds the same names into the same scope, so the
+        // names are cleared before each one to catch duplicates within it
+        let names = bound_names(&pattern.alternatives[0]);
+        for alternative in &pattern.alternatives {
+            if bound_names(alternative) != names {
+                self.error(
+                    &pattern.bar,
+                    "All alternatives in a pattern must bind the same names.",
+                );
+            }
+            if let Some(scope) = self.scopes.borrow().last() {
+                for name in &names {
+                    scope.borrow_mut().remove(name);
+                }
+            }
+            self.resolve_pattern(alternative)?;
+        }
+        Ok(())
+    }
+
+    fn visit_binding_pattern(
+        &self,
+        wrapper: Rc<Pattern>,
+        pattern: &BindingPattern,
+    ) -> Result<(), LoxResult> {
+        if *self.assigning.borrow() {
+            if self.is_constant(&pattern.name) {
+                self.error(
+                    &pattern.name,
+                    &format!("Can't assign to constant '{}'.", pattern.name.as_string()),
+                );
+            }
+            if let Some(depth) = self.local_depth(&pattern.name) {
+                self.interpreter.resolve_binding(wrapper, depth);
+            }
+            return Ok(());
+        }
+
+        self.declare(&pattern.name);
+        self.define(&pattern.name);
+        Ok(())
+    }
+
+    fn visit_class_pattern(&self, _: Rc<Pattern>, pattern: &ClassPattern) -> Result<(), LoxResult> {
+        self.resolve_expr(pattern.path.clone())?;
+        for argument in &pattern.arguments {
+            self.resolve_pattern(argument)?;
+        }
+        for field in &pattern.field_patterns {
+            self.resolve_pattern(field)?;
+        }
+        Ok(())
+    }
+
+    fn visit_list_pattern(&self, _: Rc<Pattern>, pattern: &ListPattern) -> Result<(), LoxResult> {
+        for (element, default) in pattern.elements.iter().zip(&pattern.defaults) {
+            self.resolve_pattern_default(element, default)?;
+        }
+        if let Some(rest) = &pattern.rest {
+            self.resolve_pattern(rest)?;
+        }
+        Ok(())
+    }
+
+    fn visit_literal_pattern(&self, _: Rc<Pattern>, _: &LiteralPattern) -> Result<(), LoxResult> {
+        Ok(())
+    }
+
+    fn visit_record_pattern(
+        &self,
+        _: Rc<Pattern>,
+        pattern: &RecordPattern,
+    ) -> Result<(), LoxResult> {
+        for (field, default) in pattern.patterns.iter().zip(&pattern.defaults) {
+            self.resolve_pattern_default(field, default)?;
+        }
+        Ok(())
+    }
+
+    fn visit_value_pattern(&self, _: Rc<Pattern>, pattern: &ValuePattern) -> Result<(), LoxResult> {
+        self.resolve_expr(pattern.path.clone())
+    }
+
+    fn visit_wildcard_pattern(&self, _: Rc<Pattern>, _: &WildcardPattern) -> Result<(), LoxResult> {
+        Ok(())
+    }
+}
+
+fn bound_names(pattern: &Pattern) -> Vec<String> {
+    let mut names = pattern_bindings(pattern)
+        .iter()
+        .map(Token::as_string)
+        .collect::<Vec<String>>();
+    names.sort();
+    names
+}
+
+fn pattern_token(pattern: &Pattern) -> &Token {
+    match pattern {
+        Pattern::Alternative(p) => &p.bar,
+        Pattern::Binding(p) => &p.name,
+        Pattern::Class(p) => &p.name,
+        Pattern::List(p) => &p.bracket,
+        Pattern::Literal(p) => &p.token,
+        Pattern::Record(p) => &p.brace,
+        Pattern::Value(p) => &p.name,
+        Pattern::Wildcard(p) => &p.token,
+    }
+}
+
+fn is_irrefutable(pattern: &Pattern) -> bool {
+    match pattern {
+        Pattern::Alternative(p) => p.alternatives.iter().any(|a| is_irrefutable(a)),
+        Pattern::Binding(_) | Pattern::Wildcard(_) => true,
+        _ => false,
+    }
+}
+
+// Collects the enum variants a pattern names, and whether it matches all of
+// each variant's values. Returns false if the pattern can match anything
+// else, such as a literal or a binding.
+fn enum_variants(pattern: &Pattern, variants: &mut Vec<(String, String, bool)>) -> bool {
+    let (path, complete) = match pattern {
+        Pattern::Alternative(p) => {
+            return p.alternatives.iter().all(|a| enum_variants(a, variants))
+        }
+        Pattern::Value(p) => (&p.path, true),
+        Pattern::Class(p) => (
+            &p.path,
+            p.arguments
+                .iter()
+                .chain(p.field_patterns.iter())
+                .all(|a| is_irrefutable(a)),
+        ),
+        _ => return false,
+    };
+    match path.deref() {
+        Expr::Get(get) => match get.object.deref() {
+            Expr::Variable(v) => {
+                variants.push((v.name.as_string(), get.name.as_string(), complete));
+                true
+            }
+            _ => false,
+        },
+        _ => false,
+    }
+}
@@ -54,12 +54,47 @@ impl Scanner {
             ')' => self.add_token(TokenType::RightParen),
             '{' => self.add_token(TokenType::LeftBrace),
             '}' => self.add_token(TokenType::RightBrace),
+            '[' => self.add_token(TokenType::LeftBracket),
+            ']' => self.add_token(TokenType::RightBracket),
             ',' => self.add_token(TokenType::Comma),
-            '.' => self.add_token(TokenType::Dot),
+            ':' => self.add_token(TokenType::Colon),
+            '.' => {
+                if self.peek() == Some('.') && self.peek_next() == Some('.') {
+                    self.advance();
+                    self.advance();
+                    self.add_token(TokenType::Ellipsis);
+                } else if self.is_match('.') {
+                    let tok = if self.is_match('=') {
+                        TokenType::DotDotEqual
+                    } else {
+                        TokenType::DotDot
+                    };
+                    self.add_token(tok);
+                } else {
+                    self.add_token(TokenType::Dot);
+                }
+            }
             '-' => self.add_token(TokenType::Minus),
             '+' => self.add_token(TokenType::Plus),
             ';' => self.add_token(TokenType::SemiColon),
             '*' => self.add_token(TokenType::Star),
+            '|' => {
+                let tok = if self.is_match('>') {
+                    TokenType::PipeGreater
+                } else {
+                    TokenType::Pipe
+                };
+                self.add_token(tok);
+            }
+            '?' => {
+                if self.is_match('.') {
+                    self.add_token(TokenType::QuestionDot);
+                } else if self.is_match('?') {
+                    self.add_token(TokenType::QuestionQuestion);
+                } else {
+                    return Err(LoxResult::error(self.line, "Expect '.' or '?' after '?'."));
+                }
+            }
             '!' => {
                 let tok = if self.is_match('=') {
                     TokenType::BangEqual
@@ -109,6 +144,12 @@ impl Scanner {
                     self.add_token(TokenType::Slash);
                 }
             }
+            '#' if self
+                .peek()
+                .is_some_and(|ch| ch.is_ascii_alphabetic() || ch == '_') =>
+            {
+                self.private_identifier();
+            }
             ' ' | '\r' | '\t' => {}
             '\n' => {
                 self.line += 1;
@@ -170,6 +211,13 @@ impl Scanner {
         }
     }
 
+    fn private_identifier(&mut self) {
+        while Scanner::is_alpha_numeric(self.peek()) {
+            self.advance();
+        }
+        self.add_token(TokenType::PrivateIdentifier);
+    }
+
     fn number(&mut self) {
         while Scanner::is_digit(self.peek()) {
             self.advance();
@@ -253,9 +301,9 @@ impl Scanner {
         match self.source.get(self.current) {
             Some(ch) if *ch == expected => {
                 self.current += 1;
-                return true;
+                true
             }
-            _ => return false,
+            _ => false,
         }
     }
 
@@ -269,22 +317,31 @@ impl Scanner {
 
     fn keyword(check: &str) -> Option<TokenType> {
         match check {
+            "abstract" => Some(TokenType::Abstract),
             "and" => Some(TokenType::And),
+            "case" => Some(TokenType::Case),
             "class" => Some(TokenType::Class),
+            "const" => Some(TokenType::Const),
             "else" => Some(TokenType::Else),
+            "enum" => Some(TokenType::Enum),
             "false" => Some(TokenType::False),
             "for" => Some(TokenType::For),
             "fun" => Some(TokenType::Fun),
             "if" => Some(TokenType::If),
+            "in" => Some(TokenType::In),
+            "match" => Some(TokenType::Match),
             "nil" => Some(TokenType::Nil),
             "or" => Some(TokenType::Or),
             "print" => Some(TokenType::Print),
             "return" => Some(TokenType::Return),
             "super" => Some(TokenType::Super),
             "this" => Some(TokenType::This),
+            "trait" => Some(TokenType::Trait),
             "true" => Some(TokenType::True),
             "var" => Some(TokenType::Var),
             "while" => Some(TokenType::While),
+            "with" => Some(TokenType::With),
+            "yield" => Some(TokenType::Yield),
             "break" => Some(TokenType::Break),
             _ => None,
         }
@@ -1,5 +1,6 @@
 use crate::error::*;
 use crate::expr::*;
+use crate::pattern::*;
 use crate::token::*;
 use std::rc::Rc;
 
@@ -7,13 +8,19 @@ pub enum Stmt {
     Block(Rc<BlockStmt>),
     Class(Rc<ClassStmt>),
     Break(Rc<BreakStmt>),
+    Destructure(Rc<DestructureStmt>),
+    Enum(Rc<EnumStmt>),
     Expression(Rc<ExpressionStmt>),
+    ForIn(Rc<ForInStmt>),
     Function(Rc<FunctionStmt>),
+    Match(Rc<MatchStmt>),
     If(Rc<IfStmt>),
     Print(Rc<PrintStmt>),
     Return(Rc<ReturnStmt>),
+    Trait(Rc<TraitStmt>),
     Var(Rc<VarStmt>),
     While(Rc<WhileStmt>),
+    Yield(Rc<YieldStmt>),
 }
 
 impl PartialEq for Stmt {
@@ -22,13 +29,19 @@ impl PartialEq for Stmt {
             (Stmt::Block(a), Stmt::Block(b)) => Rc::ptr_eq(a, b),
             (Stmt::Class(a), Stmt::Class(b)) => Rc::ptr_eq(a, b),
             (Stmt::Break(a), Stmt::Break(b)) => Rc::ptr_eq(a, b),
+            (Stmt::Destructure(a), Stmt::Destructure(b)) => Rc::ptr_eq(a, b),
+            (Stmt::Enum(a), Stmt::Enum(b)) => Rc::ptr_eq(a, b),
             (Stmt::Expression(a), Stmt::Expression(b)) => Rc::ptr_eq(a, b),
+            (Stmt::ForIn(a), Stmt::ForIn(b)) => Rc::ptr_eq(a, b),
             (Stmt::Function(a), Stmt::Function(b)) => Rc::ptr_eq(a, b),
+            (Stmt::Match(a), Stmt::Match(b)) => Rc::ptr_eq(a, b),
             (Stmt::If(a), Stmt::If(b)) => Rc::ptr_eq(a, b),
             (Stmt::Print(a), Stmt::Print(b)) => Rc::ptr_eq(a, b),
             (Stmt::Return(a), Stmt::Return(b)) => Rc::ptr_eq(a, b),
+            (Stmt::Trait(a), Stmt::Trait(b)) => Rc::ptr_eq(a, b),
             (Stmt::Var(a), Stmt::Var(b)) => Rc::ptr_eq(a, b),
             (Stmt::While(a), Stmt::While(b)) => Rc::ptr_eq(a, b),
+            (Stmt::Yield(a), Stmt::Yield(b)) => Rc::ptr_eq(a, b),
             _ => false,
         }
     }
@@ -52,12 +65,24 @@ impl Hash for Stmt {
             Stmt::Break(a) => {
                 hasher.write_usize(Rc::as_ptr(a) as usize);
             }
+            Stmt::Destructure(a) => {
+                hasher.write_usize(Rc::as_ptr(a) as usize);
+            }
+            Stmt::Enum(a) => {
+                hasher.write_usize(Rc::as_ptr(a) as usize);
+            }
             Stmt::Expression(a) => {
                 hasher.write_usize(Rc::as_ptr(a) as usize);
             }
+            Stmt::ForIn(a) => {
+                hasher.write_usize(Rc::as_ptr(a) as usize);
+            }
             Stmt::Function(a) => {
                 hasher.write_usize(Rc::as_ptr(a) as usize);
             }
+            Stmt::Match(a) => {
+                hasher.write_usize(Rc::as_ptr(a) as usize);
+            }
             Stmt::If(a) => {
                 hasher.write_usize(Rc::as_ptr(a) as usize);
             }
@@ -67,12 +92,18 @@ impl Hash for Stmt {
             Stmt::Return(a) => {
                 hasher.write_usize(Rc::as_ptr(a) as usize);
             }
+            Stmt::Trait(a) => {
+                hasher.write_usize(Rc::as_ptr(a) as usize);
+            }
             Stmt::Var(a) => {
                 hasher.write_usize(Rc::as_ptr(a) as usize);
             }
             Stmt::While(a) => {
                 hasher.write_usize(Rc::as_ptr(a) as usize);
             }
+            Stmt::Yield(a) => {
+                hasher.write_usize(Rc::as_ptr(a) as usize);
+            }
         }
     }
 }
@@ -87,13 +118,19 @@ impl Stmt {
             Stmt::Block(v) => stmt_visitor.visit_block_stmt(wrapper, v),
             Stmt::Class(v) => stmt_visitor.visit_class_stmt(wrapper, v),
             Stmt::Break(v) => stmt_visitor.visit_break_stmt(wrapper, v),
+            Stmt::Destructure(v) => stmt_visitor.visit_destructure_stmt(wrapper, v),
+            Stmt::Enum(v) => stmt_visitor.visit_enum_stmt(wrapper, v),
             Stmt::Expression(v) => stmt_visitor.visit_expression_stmt(wrapper, v),
+            Stmt::ForIn(v) => stmt_visitor.visit_forin_stmt(wrapper, v),
             Stmt::Function(v) => stmt_visitor.visit_function_stmt(wrapper, v),
+            Stmt::Match(v) => stmt_visitor.visit_match_stmt(wrapper, v),
             Stmt::If(v) => stmt_visitor.visit_if_stmt(wrapper, v),
             Stmt::Print(v) => stmt_visitor.visit_print_stmt(wrapper, v),
             Stmt::Return(v) => stmt_visitor.visit_return_stmt(wrapper, v),
+            Stmt::Trait(v) => stmt_visitor.visit_trait_stmt(wrapper, v),
             Stmt::Var(v) => stmt_visitor.visit_var_stmt(wrapper, v),
             Stmt::While(v) => stmt_visitor.visit_while_stmt(wrapper, v),
+            Stmt::Yield(v) => stmt_visitor.visit_yield_stmt(wrapper, v),
         }
     }
 }
@@ -104,22 +141,55 @@ pub struct BlockStmt {
 
 pub struct ClassStmt {
     pub name: Token,
+    pub fields: Option<Rc<Vec<Token>>>,
     pub superclass: Option<Rc<Expr>>,
+    pub traits: Rc<Vec<Rc<Expr>>>,
     pub methods: Rc<Vec<Rc<Stmt>>>,
+    pub abstract_methods: Rc<Vec<Token>>,
 }
 
 pub struct BreakStmt {
     pub token: Token,
 }
 
+pub struct DestructureStmt {
+    pub keyword: Option<Token>,
+    pub equals: Token,
+    pub pattern: Rc<Pattern>,
+    pub value: Rc<Expr>,
+}
+
+pub struct EnumStmt {
+    pub name: Token,
+    pub variants: Rc<Vec<Token>>,
+    pub payloads: Rc<Vec<Option<Vec<Token>>>>,
+}
+
 pub struct ExpressionStmt {
     pub expression: Rc<Expr>,
 }
 
+pub struct ForInStmt {
+    pub name: Token,
+    pub iterable: Rc<Expr>,
+    pub body: Rc<Stmt>,
+}
+
 pub struct FunctionStmt {
     pub name: Token,
     pub params: Rc<Vec<Token>>,
+    pub defaults: Rc<Vec<Option<Rc<Expr>>>>,
+    pub rest: Option<Token>,
     pub body: Rc<Vec<Rc<Stmt>>>,
+    pub is_generator: bool,
+}
+
+pub struct MatchStmt {
+    pub keyword: Token,
+    pub subject: Rc<Expr>,
+    pub patterns: Rc<Vec<Rc<Pattern>>>,
+    pub guards: Rc<Vec<Option<Rc<Expr>>>>,
+    pub bodies: Rc<Vec<Rc<Stmt>>>,
 }
 
 pub struct IfStmt {
@@ -137,9 +207,16 @@ pub struct ReturnStmt {
     pub value: Option<Rc<Expr>>,
 }
 
+pub struct TraitStmt {
+    pub name: Token,
+    pub methods: Rc<Vec<Rc<Stmt>>>,
+    pub abstract_methods: Rc<Vec<Token>>,
+}
+
 pub struct VarStmt {
     pub name: Token,
     pub initializer: Option<Rc<Expr>>,
+    pub is_const: bool,
 }
 
 pub struct WhileStmt {
@@ -147,19 +224,34 @@ pub struct WhileStmt {
     pub body: Rc<Stmt>,
 }
 
+pub struct YieldStmt {
+    pub keyword: Token,
+    pub value: Option<Rc<Expr>>,
+}
+
 pub trait StmtVisitor<T> {
     fn visit_block_stmt(&self, wrapper: Rc<Stmt>, stmt: &BlockStmt) -> Result<T, LoxResult>;
     fn visit_class_stmt(&self, wrapper: Rc<Stmt>, stmt: &ClassStmt) -> Result<T, LoxResult>;
     fn visit_break_stmt(&self, wrapper: Rc<Stmt>, stmt: &BreakStmt) -> Result<T, LoxResult>;
+    fn visit_destructure_stmt(
+        &self,
+        wrapper: Rc<Stmt>,
+        stmt: &DestructureStmt,
+    ) -> Result<T, LoxResult>;
+    fn visit_enum_stmt(&self, wrapper: Rc<Stmt>, stmt: &EnumStmt) -> Result<T, LoxResult>;
     fn visit_expression_stmt(
         &self,
         wrapper: Rc<Stmt>,
         stmt: &ExpressionStmt,
     ) -> Result<T, LoxResult>;
+    fn visit_forin_stmt(&self, wrapper: Rc<Stmt>, stmt: &ForInStmt) -> Result<T, LoxResult>;
     fn visit_function_stmt(&self, wrapper: Rc<Stmt>, stmt: &FunctionStmt) -> Result<T, LoxResult>;
+    fn visit_match_stmt(&self, wrapper: Rc<Stmt>, stmt: &MatchStmt) -> Result<T, LoxResult>;
     fn visit_if_stmt(&self, wrapper: Rc<Stmt>, stmt: &IfStmt) -> Result<T, LoxResult>;
     fn visit_print_stmt(&self, wrapper: Rc<Stmt>, stmt: &PrintStmt) -> Result<T, LoxResult>;
     fn visit_return_stmt(&self, wrapper: Rc<Stmt>, stmt: &ReturnStmt) -> Result<T, LoxResult>;
+    fn visit_trait_stmt(&self, wrapper: Rc<Stmt>, stmt: &TraitStmt) -> Result<T, LoxResult>;
     fn visit_var_stmt(&self, wrapper: Rc<Stmt>, stmt: &VarStmt) -> Result<T, LoxResult>;
     fn visit_while_stmt(&self, wrapper: Rc<Stmt>, stmt: &WhileStmt) -> Result<T, LoxResult>;
+    fn visit_yield_stmt(&self, wrapper: Rc<Stmt>, stmt: &YieldStmt) -> Result<T, LoxResult>;
 }
@@ -33,6 +33,10 @@ impl Token {
         self.ttype == ttype
     }
 
+    pub fn is_private(&self) -> bool {
+        self.ttype == TokenType::PrivateIdentifier
+    }
+
     pub fn token_type(&self) -> TokenType {
         self.ttype
     }
@@ -1,17 +1,28 @@
 #[derive(Debug, PartialEq, Clone, Copy)]
 pub enum TokenType {
+    Abstract,
     Break,
     LeftParen,
     RightParen,
     LeftBrace,
     RightBrace,
+    LeftBracket,
+    RightBracket,
     Comma,
+    Colon,
     Dot,
+    DotDot,
+    DotDotEqual,
+    Ellipsis,
     Minus,
     Plus,
     SemiColon,
     Slash,
     Star,
+    Pipe,
+    PipeGreater,
+    QuestionDot,
+    QuestionQuestion,
     Bang,
     BangEqual,
     Assign,
@@ -21,23 +32,32 @@ pub enum TokenType {
     Less,
     LessEqual,
     Identifier,
+    PrivateIdentifier,
     String,
     Number,
     And,
+    Case,
     Class,
+    Const,
     Else,
+    Enum,
     False,
     Fun,
     For,
     If,
+    In,
+    Match,
     Nil,
     Or,
     Print,
     Return,
     Super,
     This,
+    Trait,
     True,
     Var,
     While,
+    With,
+    Yield,
     Eof,
 }